@@ -0,0 +1,505 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::intcode::{Io, Program, Value};
+
+// Instructions a single command may take before we decide the game is stuck.
+const STEP_LIMIT: usize = 5_000_000;
+
+const CHECKPOINT: &str = "Security Checkpoint";
+const TOO_LIGHT: &str = "heavier than the detected value";
+const TOO_HEAVY: &str = "lighter than the detected value";
+
+// The most items the checkpoint search will try subsets of. The masks are
+// u32, and every extra item doubles the number of subsets.
+const MAX_WEIGHT_ITEMS: usize = 24;
+
+#[derive(Debug, Clone, Default)]
+struct Ascii {
+    input: VecDeque<Value>,
+    output: String,
+}
+
+impl Io for Ascii {
+    fn input(&mut self) -> Option<Value> {
+        self.input.pop_front()
+    }
+    fn output(&mut self, value: Value) {
+        self.output.push(value as u8 as char);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    // The game is waiting for the next command.
+    Prompt(String),
+    // The game has ended.
+    Halted(String),
+    // The game did not ask for another command within the step limit.
+    Stuck(String),
+}
+
+impl Response {
+    pub fn text(&self) -> &str {
+        match self {
+            Response::Prompt(s) | Response::Halted(s) | Response::Stuck(s) => s,
+        }
+    }
+}
+
+// The droid's ASCII interface. Cloning it snapshots the whole game.
+#[derive(Debug, Clone)]
+pub struct Droid {
+    program: Program<Ascii>,
+}
+
+impl Droid {
+    pub fn new(memory: Vec<Value>) -> Self {
+        Droid {
+            program: Program::new(memory, Ascii::default()),
+        }
+    }
+    fn resume(&mut self) -> Response {
+        let finished = self.program.run_limited(STEP_LIMIT);
+        let output = std::mem::take(&mut self.program.io.output);
+        if self.program.halted() {
+            Response::Halted(output)
+        } else if finished {
+            Response::Prompt(output)
+        } else {
+            Response::Stuck(output)
+        }
+    }
+    // Runs until the first prompt.
+    pub fn start(&mut self) -> Response {
+        self.resume()
+    }
+    pub fn send(&mut self, command: &str) -> Response {
+        let input = &mut self.program.io.input;
+        input.extend(command.bytes().map(Value::from));
+        input.push_back(Value::from(b'\n'));
+        self.resume()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Room {
+    pub name: String,
+    pub description: String,
+    pub doors: Vec<String>,
+    pub items: Vec<String>,
+}
+
+// Parses the last room description in some output. The droid may pass
+// through several rooms in one command, eg. when it is ejected from the
+// pressure-sensitive floor.
+pub fn parse_room(output: &str) -> Option<Room> {
+    let start = output.rfind("== ")?;
+    let mut lines = output[start..].lines();
+    let name = lines.next()?.trim_matches(|c| c == '=' || c == ' ').to_string();
+
+    let mut room = Room { name, ..Room::default() };
+    let mut description = Vec::new();
+    let mut list = None;
+    let mut in_lists = false;
+    for line in lines {
+        if line.starts_with("Doors here lead:") {
+            list = Some(&mut room.doors);
+            in_lists = true;
+        } else if line.starts_with("Items here:") {
+            list = Some(&mut room.items);
+            in_lists = true;
+        } else if let Some(entry) = line.strip_prefix("- ") {
+            if let Some(list) = &mut list {
+                list.push(entry.to_string());
+            }
+        } else if line.is_empty() || line == "Command?" {
+            list = None;
+        } else if !in_lists {
+            description.push(line);
+        }
+    }
+    room.description = description.join(" ");
+    Some(room)
+}
+
+// Parses the response to the `inv` command.
+pub fn parse_inventory(output: &str) -> Option<Vec<String>> {
+    if output.contains("You aren't carrying any items.") {
+        return Some(Vec::new());
+    }
+    let start = output.find("Items in your inventory:")?;
+    Some(output[start..].lines()
+        .skip(1)
+        .skip_while(|line| line.is_empty())
+        .map_while(|line| line.strip_prefix("- "))
+        .map(String::from)
+        .collect())
+}
+
+pub fn reverse(dir: &str) -> &'static str {
+    match dir {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        _ => panic!("Unknown direction: {}", dir),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Go(String),
+    Take(String),
+    Drop(String),
+    Inventory,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Go(dir) => write!(f, "{}", dir),
+            Command::Take(item) => write!(f, "take {}", item),
+            Command::Drop(item) => write!(f, "drop {}", item),
+            Command::Inventory => write!(f, "inv"),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        Ok(match s {
+            "north" | "n" => Command::Go("north".into()),
+            "south" | "s" => Command::Go("south".into()),
+            "east" | "e" => Command::Go("east".into()),
+            "west" | "w" => Command::Go("west".into()),
+            "inv" | "i" => Command::Inventory,
+            _ => if let Some(item) = s.strip_prefix("take ") {
+                Command::Take(item.trim().into())
+            } else if let Some(item) = s.strip_prefix("drop ") {
+                Command::Drop(item.trim().into())
+            } else {
+                return Err(format!("Unknown command: {:?}", s));
+            },
+        })
+    }
+}
+
+// The rooms discovered so far and how they connect.
+#[derive(Debug, Clone, Default)]
+pub struct ShipGraph {
+    rooms: BTreeMap<String, Room>,
+    // For each room, where each of its doors lead (if known yet).
+    doors: BTreeMap<String, BTreeMap<String, Option<String>>>,
+}
+
+impl ShipGraph {
+    pub fn visit(&mut self, room: &Room) {
+        self.rooms.insert(room.name.clone(), room.clone());
+        let doors = self.doors.entry(room.name.clone()).or_default();
+        for door in &room.doors {
+            doors.entry(door.clone()).or_insert(None);
+        }
+    }
+    pub fn link(&mut self, from: &str, dir: &str, to: &str) {
+        self.doors.entry(from.into()).or_default().insert(dir.into(), Some(to.into()));
+        self.doors.entry(to.into()).or_default().insert(reverse(dir).into(), Some(from.into()));
+    }
+    pub fn room(&self, name: &str) -> Option<&Room> {
+        self.rooms.get(name)
+    }
+    pub fn rooms(&self) -> impl Iterator<Item=&Room> {
+        self.rooms.values()
+    }
+    // Where a door leads, if anyone has been through it.
+    pub fn door(&self, room: &str, dir: &str) -> Option<&str> {
+        self.doors.get(room)?.get(dir)?.as_deref()
+    }
+    // The doors to go through to get from `from` to `to`.
+    pub fn route(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut came_from: HashMap<&str, (&str, &str)> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+        while let Some(room) = queue.pop_front() {
+            if room == to {
+                let mut route = Vec::new();
+                let mut room = to;
+                while room != from {
+                    let (prev, dir) = came_from[room];
+                    route.push(dir.to_string());
+                    room = prev;
+                }
+                route.reverse();
+                return Some(route);
+            }
+            for (dir, next) in self.doors.get(room)? {
+                if let Some(next) = next {
+                    if next != from && !came_from.contains_key(next.as_str()) {
+                        came_from.insert(next, (room, dir));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+    // Graphviz output. Doors which have not been explored yet lead to
+    // anonymous point nodes.
+    pub fn to_dot(&self) -> String {
+        fn quote(s: &str) -> String {
+            format!("\"{}\"", s.replace('"', "\\\""))
+        }
+
+        let mut s = String::from("digraph ship {\n");
+        for room in self.rooms.values() {
+            let mut label = quote(&room.name);
+            if !room.items.is_empty() {
+                label = quote(&format!("{}\\n{}", room.name, room.items.join(", ")));
+            }
+            s += &format!("    {} [label={}];\n", quote(&room.name), label);
+        }
+        for (from, doors) in &self.doors {
+            for (dir, to) in doors {
+                match to {
+                    Some(to) => {
+                        s += &format!("    {} -> {} [label={}];\n", quote(from), quote(to), quote(dir));
+                    },
+                    None => {
+                        let unknown = quote(&format!("{}:{}", from, dir));
+                        s += &format!("    {} [shape=point];\n", unknown);
+                        s += &format!("    {} -> {} [label={}, style=dashed];\n", quote(from), unknown, quote(dir));
+                    },
+                }
+            }
+        }
+        s += "}\n";
+        s
+    }
+}
+
+impl fmt::Display for ShipGraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, doors) in &self.doors {
+            write!(f, "{}", name)?;
+            if let Some(room) = self.rooms.get(name).filter(|r| !r.items.is_empty()) {
+                write!(f, " [{}]", room.items.join(", "))?;
+            }
+            writeln!(f)?;
+            for (dir, to) in doors {
+                writeln!(f, "    {} -> {}", dir, to.as_deref().unwrap_or("?"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Wraps the droid with a command layer which keeps track of where the
+// droid is, what it is carrying and what it has discovered.
+#[derive(Debug, Clone)]
+pub struct Session {
+    droid: Droid,
+    pub graph: ShipGraph,
+    pub current: Option<String>,
+    pub inventory: Vec<String>,
+    pub log: Vec<Command>,
+}
+
+impl Session {
+    pub fn new(memory: Vec<Value>) -> Self {
+        Session {
+            droid: Droid::new(memory),
+            graph: ShipGraph::default(),
+            current: None,
+            inventory: Vec::new(),
+            log: Vec::new(),
+        }
+    }
+    pub fn start(&mut self) -> Response {
+        let response = self.droid.start();
+        self.arrive(None, response.text());
+        response
+    }
+    // A copy of the game to try things out in.
+    pub fn snapshot(&self) -> Droid {
+        self.droid.clone()
+    }
+    fn arrive(&mut self, dir: Option<&str>, output: &str) {
+        if let Some(room) = parse_room(output) {
+            if let (Some(prev), Some(dir)) = (&self.current, dir) {
+                if prev != &room.name {
+                    self.graph.link(prev, dir, &room.name);
+                }
+            }
+            self.graph.visit(&room);
+            self.current = Some(room.name);
+        }
+    }
+    fn current_room_mut(&mut self) -> Option<&mut Room> {
+        let name = self.current.as_ref()?;
+        self.graph.rooms.get_mut(name)
+    }
+    pub fn send(&mut self, command: &Command) -> Response {
+        self.log.push(command.clone());
+        let response = self.droid.send(&command.to_string());
+        let text = response.text();
+        match command {
+            Command::Go(dir) => self.arrive(Some(dir), text),
+            Command::Take(item) => if text.contains("You take the") {
+                self.inventory.push(item.clone());
+                if let Some(room) = self.current_room_mut() {
+                    room.items.retain(|i| i != item);
+                }
+            },
+            Command::Drop(item) => if text.contains("You drop the") {
+                self.inventory.retain(|i| i != item);
+                if let Some(room) = self.current_room_mut() {
+                    room.items.push(item.clone());
+                }
+            },
+            Command::Inventory => if let Some(items) = parse_inventory(text) {
+                self.inventory = items;
+            },
+        }
+        response
+    }
+}
+
+// Plays the game without a human: maps the ship, collects every item which
+// does not end or break the game, then finds the right combination of items
+// to get past the pressure-sensitive floor.
+#[derive(Debug)]
+pub struct Solver {
+    pub session: Session,
+    pub traps: HashSet<String>,
+    explored: HashSet<String>,
+    // The checkpoint room and the door to the pressure-sensitive floor.
+    checkpoint: Option<(String, String)>,
+}
+
+impl Solver {
+    pub fn new(memory: Vec<Value>) -> Self {
+        Solver {
+            session: Session::new(memory),
+            traps: HashSet::new(),
+            explored: HashSet::new(),
+            checkpoint: None,
+        }
+    }
+    fn here(&self) -> String {
+        self.session.current.clone().expect("Droid is not in a room")
+    }
+    fn go(&mut self, dir: &str) -> String {
+        self.session.send(&Command::Go(dir.into()));
+        self.here()
+    }
+    // Tries taking an item in a snapshot of the game, and checks that the
+    // game is still running and the droid can still move afterwards.
+    fn is_safe(&self, item: &str, room: &Room) -> bool {
+        let mut droid = self.session.snapshot();
+        match droid.send(&Command::Take(item.into()).to_string()) {
+            Response::Prompt(_) => {},
+            _ => return false,
+        }
+        let dir = match room.doors.first() {
+            Some(dir) => dir,
+            None => return true,
+        };
+        match droid.send(dir) {
+            Response::Prompt(s) => parse_room(&s).is_some_and(|r| r.name != room.name),
+            _ => false,
+        }
+    }
+    fn collect_items(&mut self, room: &Room) {
+        for item in &room.items {
+            if self.is_safe(item, room) {
+                self.session.send(&Command::Take(item.clone()));
+            } else {
+                self.traps.insert(item.clone());
+            }
+        }
+    }
+    fn explore(&mut self) {
+        let here = self.here();
+        let room = self.session.graph.room(&here).cloned().expect("Unknown room");
+        self.explored.insert(here.clone());
+        self.collect_items(&room);
+        for dir in room.doors {
+            if self.session.graph.door(&here, &dir).is_some() {
+                continue;
+            }
+            let next = self.go(&dir);
+            if next == here {
+                // Bounced straight back: this is the pressure-sensitive floor.
+                self.checkpoint = Some((here.clone(), dir));
+                continue;
+            }
+            if !self.explored.contains(&next) {
+                self.explore();
+            }
+            self.go(reverse(&dir));
+        }
+    }
+    // Searches subsets of the inventory until the pressure-sensitive floor
+    // lets the droid through. Any set that is too heavy rules out all of its
+    // supersets, and any set that is too light rules out all of its subsets.
+    // Gives up with more than `MAX_WEIGHT_ITEMS` items, which would be too
+    // many subsets to try.
+    fn find_weight(&mut self, dir: &str) -> Option<String> {
+        let items = self.session.inventory.clone();
+        if items.len() > MAX_WEIGHT_ITEMS {
+            return None;
+        }
+        let mut held: u32 = (1 << items.len()) - 1;
+        let mut too_heavy: Vec<u32> = Vec::new();
+        let mut too_light: Vec<u32> = Vec::new();
+
+        for mask in 0..(1u32 << items.len()) {
+            if too_heavy.iter().any(|&h| h & !mask == 0) || too_light.iter().any(|&l| mask & !l == 0) {
+                continue;
+            }
+            for (i, item) in items.iter().enumerate() {
+                let bit = 1 << i;
+                if held & bit != 0 && mask & bit == 0 {
+                    self.session.send(&Command::Drop(item.clone()));
+                } else if held & bit == 0 && mask & bit != 0 {
+                    self.session.send(&Command::Take(item.clone()));
+                }
+            }
+            held = mask;
+
+            match self.session.send(&Command::Go(dir.into())) {
+                Response::Halted(s) => return parse_password(&s),
+                Response::Prompt(s) if s.contains(TOO_LIGHT) => too_light.push(mask),
+                Response::Prompt(s) if s.contains(TOO_HEAVY) => too_heavy.push(mask),
+                other => panic!("Unexpected response at the checkpoint: {}", other.text()),
+            }
+        }
+        None
+    }
+    // Plays the whole game, returning the password for the main airlock.
+    pub fn solve(&mut self) -> Option<String> {
+        self.session.start();
+        self.explore();
+
+        let (checkpoint, dir) = self.checkpoint.clone()?;
+        debug_assert_eq!(checkpoint, CHECKPOINT);
+        for step in self.session.graph.route(&self.here(), &checkpoint)? {
+            self.go(&step);
+        }
+        self.find_weight(&dir)
+    }
+}
+
+fn parse_password(output: &str) -> Option<String> {
+    let start = output.find("typing ")? + "typing ".len();
+    let password: String = output[start..].chars().take_while(char::is_ascii_digit).collect();
+    if password.is_empty() {
+        None
+    } else {
+        Some(password)
+    }
+}
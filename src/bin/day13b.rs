@@ -1,219 +1,10 @@
 use std::collections::HashMap;
-use std::time::Duration;
 
-const INPUT: &str = include_str!("../../inputs/day13.txt");
-
-type Value = i64;
-
-trait Io {
-    fn input(&mut self) -> Value;
-    fn output(&mut self, value: Value);
-}
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum OpCode {
-    Add,
-    Mul,
-    Input,
-    Output,
-    JumpIfTrue,
-    JumpIfFalse,
-    LessThan,
-    Equals,
-    AdjustRbo,
-}
-
-impl OpCode {
-    fn decode(input: &mut Value) -> Self {
-        use OpCode::*;
-        let res = match *input % 100 {
-            1 => Add,
-            2 => Mul,
-            3 => Input,
-            4 => Output,
-            5 => JumpIfTrue,
-            6 => JumpIfFalse,
-            7 => LessThan,
-            8 => Equals,
-            9 => AdjustRbo,
-            other => panic!("Unknown instruction: {}", other),
-        };
-        *input /= 100;
-        res
-    }
-    fn len(&self) -> usize {
-        use OpCode::*;
-        match self {
-            Add | Mul | LessThan | Equals => 3,
-            Input | Output | AdjustRbo => 1,
-            JumpIfTrue | JumpIfFalse => 2,
-        }
-    }
-}
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum ParameterMode {
-    Positional,
-    Immediate,
-    Relative,
-}
-
-impl ParameterMode {
-    fn decode(input: &mut Value) -> Self {
-        use ParameterMode::*;
-        let res = match *input % 10 {
-            0 => Positional,
-            1 => Immediate,
-            2 => Relative,
-            other => panic!("Unknown parameter mode: {}", other),
-        };
-        *input /= 10;
-        res
-    }
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Parameter {
-    value: Value,
-    mode: ParameterMode,
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Op {
-    code: OpCode,
-    args: [Parameter; 4],
-}
-
-#[derive(Debug)]
-struct Program<T: Io> {
-    memory: Vec<Value>,
-    pc: usize,
-    rbo: Value,
-    io: T,
-}
+use aoc2019::intcode::{self, Io, Program, Value};
+use aoc2019::record;
+use aoc2019::render::{Frame, Glyph, Screen};
 
-impl<T: Io> Program<T> {
-    fn new(memory: Vec<Value>, io: T) -> Self {
-        Program {
-            memory,
-            pc: 0,
-            rbo: 0,
-            io,
-        }
-    }
-    fn read_and_advance(&mut self) -> Value {
-        let res = self.memory[self.pc];
-        self.pc += 1;
-        res
-    }
-    fn decode_op(&mut self) -> Op {
-        let mut value = self.read_and_advance();
-        let op_code = OpCode::decode(&mut value);
-        let mut op = Op {
-            code: op_code,
-            args: [Parameter {
-                value: 0,
-                mode: ParameterMode::Positional,
-            }; 4],
-        };
-        for i in 0..op_code.len() {
-            let param = self.read_and_advance();
-            let mode = ParameterMode::decode(&mut value);
-            op.args[i] = Parameter {
-                value: param,
-                mode,
-            };
-        }
-        op
-    }
-    fn read_memory(&self, addr: Value) -> Value {
-        if addr < 0 { panic!("Read from negative address: {}", addr) }
-        let addr = addr as usize;
-        if addr >= self.memory.len() {
-            0
-        } else {
-            self.memory[addr]
-        }
-    }
-    fn write_memory(&mut self, addr: Value, value: Value) {
-        if addr < 0 { panic!("Write to negative address: {}", addr) }
-        let addr = addr as usize;
-        if addr >= self.memory.len() {
-            self.memory.resize(addr+1, 0);
-        }
-        self.memory[addr] = value;
-    }
-    fn read_param(&self, param: Parameter) -> Value {
-        match param.mode {
-            ParameterMode::Positional => self.read_memory(param.value),
-            ParameterMode::Immediate => param.value,
-            ParameterMode::Relative => self.read_memory(param.value + self.rbo),
-        }
-    }
-    fn write_param(&mut self, param: Parameter, v: Value) {
-        match param.mode {
-            ParameterMode::Positional => self.write_memory(param.value, v),
-            ParameterMode::Immediate => panic!("Cannot write to immedaite parameter"),
-            ParameterMode::Relative => self.write_memory(param.value + self.rbo, v),
-        }
-    }
-    fn execute(&mut self, op: Op) {
-        match op.code {
-            OpCode::Add => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                self.write_param(op.args[2], a+b);
-            },
-            OpCode::Mul => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                self.write_param(op.args[2], a*b);
-            },
-            OpCode::Input => {
-                let a = self.io.input();
-                self.write_param(op.args[0], a);
-            },
-            OpCode::Output => {
-                let a = self.read_param(op.args[0]);
-                self.io.output(a);
-            },
-            OpCode::JumpIfTrue => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                if a != 0 {
-                    self.pc = b as usize;
-                }
-            },
-            OpCode::JumpIfFalse => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                if a == 0 {
-                    self.pc = b as usize;
-                }
-            },
-            OpCode::LessThan => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                self.write_param(op.args[2], if a < b { 1 } else { 0 });
-            },
-            OpCode::Equals => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                self.write_param(op.args[2], if a == b { 1 } else { 0 });
-            },
-            OpCode::AdjustRbo => {
-                let a = self.read_param(op.args[0]);
-                self.rbo += a;
-            },
-        }
-    }
-    fn run(&mut self) {
-        while self.memory[self.pc] != 99 {
-            let op = self.decode_op();
-            self.execute(op);
-        }
-    }
-}
+const INPUT: &str = include_str!("../../inputs/day13.txt");
 
 #[derive(Debug, Copy, Clone)]
 enum OutState {
@@ -222,63 +13,78 @@ enum OutState {
     TileId
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Policy {
+    // Chase the ball's current column every frame.
+    Naive,
+    // Simulate ahead to find where the ball will land and move there directly.
+    Predictive,
+}
+
+#[derive(Debug, Clone)]
 struct IoState {
     screen: HashMap<(i64, i64), Value>,
     x: i64,
     y: i64,
     out_state: OutState,
     ball_x: i64,
+    ball_y: i64,
     paddle_x: i64,
+    paddle_y: i64,
     score: i64,
+    joystick: Option<Value>,
 }
 
 impl IoState {
     fn new() -> Self {
-        print!("\n\x1B[s");
         IoState {
             screen: HashMap::new(),
             x: 0,
             y: 0,
             out_state: OutState::X,
             ball_x: 0,
+            ball_y: 0,
             paddle_x: 0,
+            paddle_y: 0,
             score: 0,
+            joystick: None,
         }
     }
+    fn blocks(&self) -> usize {
+        self.screen.values().filter(|&&v| v == 2).count()
+    }
+    fn ball_landing(&self) -> bool {
+        self.ball_y == self.paddle_y - 1
+    }
 }
 
-impl IoState {
-    fn display(&self) {
-        let min_x = self.screen.keys().map(|&(x, _)| x).min().unwrap_or(0);
-        let max_x = self.screen.keys().map(|&(x, _)| x).max().unwrap_or(0);
-        let min_y = self.screen.keys().map(|&(_, y)| y).min().unwrap_or(0);
-        let max_y = self.screen.keys().map(|&(_, y)| y).max().unwrap_or(0);
-
-        let mut s = String::new();
-        s += "\x1B[u";
-        for y in min_y..=max_y {
-            for x in min_x..=max_x {
-                match self.screen.get(&(x, y)).unwrap_or(&0) {
-                    0 => s.push(' '),
-                    1 => s.push('\u{2588}'),
-                    2 => s.push('#'),
-                    3 => s.push('='),
-                    4 => s.push('o'),
-                    _ => s.push('?'),
-                }
-            }
-            s.push('\n');
+#[derive(Debug, Copy, Clone)]
+struct Tile(Value);
+
+impl Glyph for Tile {
+    fn glyph(&self) -> char {
+        match self.0 {
+            0 => ' ',
+            1 => '\u{2588}',
+            2 => '#',
+            3 => '=',
+            4 => 'o',
+            _ => '?',
         }
-        println!("{}\n\n{}", s, self.score);
-        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+impl IoState {
+    fn frame(&self) -> Frame {
+        let mut frame = Frame::from_tiles(self.screen.iter().map(|(&pos, &v)| (pos, Tile(v))));
+        frame.status = self.score.to_string();
+        frame
     }
 }
 
 impl Io for IoState {
-    fn input(&mut self) -> Value {
-        self.display();
-        (self.ball_x - self.paddle_x).signum()
+    fn input(&mut self) -> Option<Value> {
+        self.joystick.take()
     }
     fn output(&mut self, value: Value) {
         let new_state = match self.out_state {
@@ -292,8 +98,10 @@ impl Io for IoState {
 
                     if value == 4 {
                         self.ball_x = self.x;
+                        self.ball_y = self.y;
                     } else if value == 3 {
                         self.paddle_x = self.x;
+                        self.paddle_y = self.y;
                     }
                 }
 
@@ -304,15 +112,89 @@ impl Io for IoState {
     }
 }
 
+// Runs a snapshot of the game forward without moving the paddle, and returns
+// the column the ball will be in when it next reaches the row above the paddle.
+fn predict_landing(program: &Program<IoState>) -> Option<Value> {
+    let mut sim = program.clone();
+    loop {
+        sim.io.joystick = Some(0);
+        sim.run();
+        if sim.halted() {
+            return None;
+        }
+        if sim.io.ball_landing() {
+            return Some(sim.io.ball_x);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Outcome {
+    score: Value,
+    blocks: usize,
+    frames: usize,
+    moves: usize,
+}
+
+fn play(memory: &[Value], policy: Policy, mut screen: Screen) -> Outcome {
+    let mut program = Program::new(memory.to_vec(), IoState::new());
+    let mut target = None;
+    let mut frames = 0;
+    let mut moves = 0;
+
+    program.run();
+    while !program.halted() {
+        screen.draw(program.io.frame());
+
+        let io = &program.io;
+        let goal = match policy {
+            Policy::Naive => io.ball_x,
+            Policy::Predictive => if io.ball_landing() {
+                target = None;
+                io.ball_x
+            } else {
+                if target.is_none() {
+                    target = predict_landing(&program);
+                }
+                target.unwrap_or(io.paddle_x)
+            },
+        };
+        let joystick = (goal - io.paddle_x).signum();
+
+        frames += 1;
+        if joystick != 0 {
+            moves += 1;
+        }
+        program.io.joystick = Some(joystick);
+        program.run();
+    }
+
+    screen.draw(program.io.frame());
+
+    Outcome {
+        score: program.io.score,
+        blocks: program.io.blocks(),
+        frames,
+        moves,
+    }
+}
+
 fn main() {
-    let mut memory: Vec<Value> = INPUT.trim().split(",")
-        .map(|n| n.parse().unwrap())
-        .collect();
+    let mut memory = intcode::parse(INPUT);
     
     memory[0] = 2;
-    
-    let mut program = Program::new(memory, IoState::new());
-    program.run();
 
-    program.io.display();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let record = record::take_arg(&mut args);
+    let show = args.iter().any(|arg| arg == "--show");
+
+    let naive = play(&memory, Policy::Naive, Screen::headless());
+    let predictive = play(&memory, Policy::Predictive, record::screen(show, 200, record.as_deref()));
+
+    for (name, outcome) in &[("naive", naive), ("predictive", predictive)] {
+        println!(
+            "{:>10}: score {} ({} blocks left), {} frames, {} moves",
+            name, outcome.score, outcome.blocks, outcome.frames, outcome.moves
+        );
+    }
 }
@@ -1,92 +1,59 @@
-use std::collections::{HashSet, HashMap};
+use std::collections::BTreeMap;
+use std::fs;
+
+use aoc2019::nanofactory::{Nanofactory, FUEL};
 
 const INPUT: &str = include_str!("../../inputs/day14.txt");
 
-#[derive(Debug, Clone)]
-struct Component {
-    material: String,
-    amount: i64,
+// Parses `--raw` values such as `ORE=1,CLAY=3`.
+fn parse_raw(s: &str) -> Result<BTreeMap<String, u64>, String> {
+    s.split(',').map(|item| {
+        let mut parts = item.splitn(2, '=');
+        let chemical = parts.next().unwrap_or("").trim();
+        let cost = parts.next().ok_or_else(|| format!("Expected CHEMICAL=COST, got {:?}", item))?;
+        let cost = cost.trim().parse().map_err(|e| format!("Invalid cost {:?}: {}", cost, e))?;
+        Ok((chemical.into(), cost))
+    }).collect()
 }
 
-impl Component {
-    fn new(s: &str) -> Self {
-        let mut s = s.splitn(2, " ");
-        Component {
-            amount: s.next().unwrap().parse().unwrap(),
-            material: s.next().unwrap().into(),
-        }
+// The value following `--name`, if the flag was given.
+fn arg<'a>(args: &'a [String], name: &str, value: &str) -> Result<Option<&'a str>, String> {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) => args.get(i + 1)
+            .map(|arg| Some(arg.as_str()))
+            .ok_or_else(|| format!("Usage: {} <{}>", name, value)),
+        None => Ok(None),
     }
 }
 
-#[derive(Debug, Clone)]
-struct Reaction {
-    inputs: Vec<Component>,
-    output: Component,
-}
-
-impl Reaction {
-    fn new(line: &str) -> Self {
-        let mut line = line.splitn(2, " => ");
-        let lhs = line.next().unwrap();
-        let rhs = line.next().unwrap();
-
-        let inputs = lhs.split(", ").map(Component::new).collect();
-        Reaction {
-            inputs,
-            output: Component::new(rhs),
-        }
+fn run(args: &[String]) -> Result<(), String> {
+    let input = match arg(args, "--input", "file")? {
+        Some(path) => fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?,
+        None => INPUT.into(),
+    };
+    let factory = match arg(args, "--raw", "CHEMICAL=COST,...")? {
+        Some(raw) => Nanofactory::parse_with_raw(&input, parse_raw(raw)?)?,
+        None => Nanofactory::parse(&input)?,
+    };
+    if let Some(path) = arg(args, "--dot", "file")? {
+        fs::write(path, factory.to_dot()).map_err(|e| format!("Failed to save {}: {}", path, e))?;
     }
-}
 
-struct Process {
-    reactions: HashMap<String, Reaction>,
-    amounts: HashMap<String, i64>,
-    queued: HashSet<String>,
-}
-
-impl Process {
-    fn step(&mut self, mat: &str) {
-        self.queued.remove(mat);
-        let reaction = self.reactions[mat].clone();
-        if let Some(amount) = self.amounts.get_mut(mat) {
-            if *amount < 0 {
-                let multiple = (reaction.output.amount - *amount - 1) / reaction.output.amount;
-                *amount += multiple * reaction.output.amount;
-                for input in reaction.inputs {
-                    self.take(&input.material, input.amount * multiple);
-                }
-            }
-        }
-    }
-    fn take(&mut self, material: &str, amount: i64) {
-        let amount_mut = self.amounts.entry(material.into()).or_insert(0);
-        *amount_mut -= amount;
-        if *amount_mut < 0 && material != "ORE" {
-            self.queued.insert(material.into());
-        }
-    }
-    fn run(&mut self) {
-        while let Some(mat) = self.queued.iter().next().map(|s| s.to_owned()) {
-            self.step(&mat);
-        }
+    let plan = factory.plan(FUEL, 1);
+    print!("{}", plan);
+    println!("{}", plan.cost);
+    if !plan.exact {
+        println!("(there may be a cheaper plan)");
     }
+    Ok(())
 }
 
 fn main() {
-    let mut reactions = HashMap::new();
-    for line in INPUT.lines() {
-        let reaction = Reaction::new(line);
-        let output_material = reaction.output.material.clone();
-        reactions.insert(output_material, reaction);
+    // An optional `--input <file>` replaces the puzzle input, and `--raw`
+    // lists its raw materials with their costs, allowing alternative
+    // reactions. `--dot <file>` exports the reaction graph.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        println!("{}", e);
     }
-
-    let mut process = Process {
-        reactions,
-        amounts: HashMap::new(),
-        queued: HashSet::new(),
-    };
-    process.take("FUEL", 1);
-
-    process.run();
-    println!("{:?}", process.amounts);
 }
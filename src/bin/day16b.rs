@@ -1,30 +1,28 @@
-const INPUT: &str = include_str!("../../inputs/day16.txt");
+use aoc2019::fft;
 
-fn next_seq(input: &mut [i32]) {
-    let mut sum = 0;
-    for x in input {
-        let y = *x;
-        *x = (y + sum) % 10;
-        sum += y;
-    }
-}
+const INPUT: &str = include_str!("../../inputs/day16.txt");
 
 fn main() {
-    let offset: usize = INPUT[0..7].parse().unwrap();
-    let mut seq: Vec<_> = INPUT.chars()
-        .filter_map(|c| c.to_digit(10))
-        .map(|d| d as i32)
-        .collect();
-    
-    let actual_len = seq.len()*10000;
-    seq = seq.into_iter().cycle().skip(offset).take(actual_len - offset).collect();
-    seq.reverse();
+    let signal = fft::parse(INPUT);
+    let offset: usize = fft::to_string(&signal[..7]).parse().unwrap();
 
-    for _ in 0..100 {
-        next_seq(&mut seq);
-        let start: String = seq.iter().rev().take(8).map(|v| v.to_string()).collect();
-        let end: String = seq.iter().take(8).rev().map(|v| v.to_string()).collect();
+    // `--closed-form` computes the message from binomial coefficients rather
+    // than running every phase, and `--check` compares the two.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let has = |name: &str| args.iter().any(|arg| arg == name);
 
-        println!("{}...{}", start, end);
+    let message = if has("--closed-form") {
+        fft::closed_form_window(&signal, 10000, 100, offset, 8).expect("Offset is in the first half of the signal")
+    } else {
+        fft::window(&signal, 10000, 100, offset, 8)
+    };
+    if has("--check") {
+        let iterative = fft::window(&signal, 10000, 100, offset, 8);
+        match fft::closed_form_window(&signal, 10000, 100, offset, 8) {
+            Some(closed) if closed == iterative => println!("Closed form matches"),
+            Some(closed) => println!("Closed form gives {}, but iterating gives {}", fft::to_string(&closed), fft::to_string(&iterative)),
+            None => println!("Offset is in the first half of the signal, so there is no closed form"),
+        }
     }
+    println!("{}", fft::to_string(&message));
 }
@@ -0,0 +1,142 @@
+use aoc2019::capture::{Capture, Filter};
+use aoc2019::intcode;
+use aoc2019::network::{Network, FirstPacket, RepeatDetector, NAT_ADDRESS};
+
+const INPUT: &str = include_str!("../../inputs/day23.txt");
+const COUNT: usize = 50;
+
+const USAGE: &str = "\
+Usage:
+    day23capture record <file> [a|b]
+    day23capture summary <file>
+    day23capture packets <file> [--src N] [--dst N] [--from TICK] [--to TICK]
+    day23capture queues <file>
+    day23capture nat <file>";
+
+fn record(path: &str, part: &str) -> Result<(), String> {
+    let memory = intcode::parse(INPUT);
+    let capture = match part {
+        "a" => {
+            let mut network = Network::new(&memory, COUNT, FirstPacket::default()).with_log();
+            network.run();
+            network.capture()
+        },
+        "b" => {
+            let mut network = Network::new(&memory, COUNT, RepeatDetector::default()).with_log();
+            network.run();
+            network.capture()
+        },
+        _ => return Err(USAGE.into()),
+    };
+    capture.save(path).map_err(|e| format!("Failed to save {}: {}", path, e))?;
+    println!("Captured {} packets over {} ticks", capture.packets.len(), capture.depths.len());
+    Ok(())
+}
+
+fn summary(capture: &Capture) {
+    let summary = capture.summary();
+    println!("machines:  {}", capture.count);
+    println!("ticks:     {}", summary.ticks);
+    println!("packets:   {}", summary.packets);
+    println!("NAT wakes: {}", summary.nat_wakes);
+    println!("max queue: {}", summary.max_depth);
+    println!();
+    println!("address     sent  received");
+    let addresses = summary.sent.keys().chain(summary.received.keys());
+    let mut addresses: Vec<_> = addresses.copied().collect();
+    addresses.sort();
+    addresses.dedup();
+    for address in addresses {
+        println!(
+            "{:>7}  {:>7}  {:>8}",
+            address,
+            summary.sent.get(&address).unwrap_or(&0),
+            summary.received.get(&address).unwrap_or(&0)
+        );
+    }
+}
+
+fn packets(capture: &Capture, args: &[String]) -> Result<(), String> {
+    let mut filter = Filter::default();
+    for pair in args.chunks(2) {
+        let value = pair.get(1).and_then(|v| v.parse().ok());
+        match (pair[0].as_str(), value) {
+            ("--src", Some(v)) => filter.src = Some(v),
+            ("--dst", Some(v)) => filter.dst = Some(v),
+            ("--from", Some(v)) => filter.from_tick = Some(v),
+            ("--to", Some(v)) => filter.to_tick = Some(v),
+            _ => return Err(format!("Bad filter: {}\n{}", pair.join(" "), USAGE)),
+        }
+    }
+    println!("{:>6} {:>4} -> {:<4} {:>12} {:>12}", "tick", "src", "dst", "x", "y");
+    for p in capture.filter(&filter) {
+        println!("{:>6} {:>4} -> {:<4} {:>12} {:>12}", p.tick, p.src, p.dst, p.x, p.y);
+    }
+    Ok(())
+}
+
+// One character per machine: the queue depth, or `+` if it is above nine.
+// Only ticks where some queue changed are shown.
+fn queues(capture: &Capture) {
+    let mut prev: Option<&Vec<usize>> = None;
+    for (tick, depths) in capture.depths.iter().enumerate() {
+        if prev == Some(depths) {
+            continue;
+        }
+        prev = Some(depths);
+        let row: String = depths.iter()
+            .map(|&d| if d > 9 { '+' } else { (b'0' + d as u8) as char })
+            .collect();
+        println!("{:>6} {:>4} {}", tick, depths.iter().sum::<usize>(), row);
+    }
+}
+
+fn nat(capture: &Capture) {
+    for wake in capture.nat_wakes() {
+        for p in &wake.received {
+            println!("{:>6}   {:>3} -> NAT   {:>12} {:>12}", p.tick, p.src, p.x, p.y);
+        }
+        let p = wake.packet;
+        println!(
+            "{:>6}   NAT -> {:<3}   {:>12} {:>12}  WAKE{}",
+            p.tick, p.dst, p.x, p.y,
+            if wake.repeat { " (repeated y)" } else { "" }
+        );
+    }
+    let pending = capture.packets.iter()
+        .rev()
+        .take_while(|p| p.src != NAT_ADDRESS)
+        .filter(|p| p.dst == NAT_ADDRESS)
+        .count();
+    if pending > 0 {
+        println!("{} packets received after the last wake-up", pending);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    if args.len() < 2 {
+        return Err(USAGE.into());
+    }
+    let (command, path) = (args[0].as_str(), args[1].as_str());
+
+    if command == "record" {
+        return record(path, args.get(2).map(String::as_str).unwrap_or("b"));
+    }
+
+    let capture = Capture::load(path).map_err(|e| format!("Failed to load {}: {}", path, e))?;
+    match command {
+        "summary" => summary(&capture),
+        "packets" => packets(&capture, &args[2..])?,
+        "queues" => queues(&capture),
+        "nat" => nat(&capture),
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        println!("{}", e);
+    }
+}
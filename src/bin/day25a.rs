@@ -1,256 +1,60 @@
-use std::io;
+use std::fs;
+use std::io::{self, BufRead};
 
-const INPUT: &str = include_str!("../../inputs/day25.txt");
-
-type Value = i64;
-
-trait Io {
-    fn input(&mut self) -> Option<Value>;
-    fn output(&mut self, value: Value);
-}
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum OpCode {
-    Add,
-    Mul,
-    Input,
-    Output,
-    JumpIfTrue,
-    JumpIfFalse,
-    LessThan,
-    Equals,
-    AdjustRbo,
-}
-
-impl OpCode {
-    fn decode(input: &mut Value) -> Self {
-        use OpCode::*;
-        let res = match *input % 100 {
-            1 => Add,
-            2 => Mul,
-            3 => Input,
-            4 => Output,
-            5 => JumpIfTrue,
-            6 => JumpIfFalse,
-            7 => LessThan,
-            8 => Equals,
-            9 => AdjustRbo,
-            other => panic!("Unknown instruction: {}", other),
-        };
-        *input /= 100;
-        res
-    }
-    fn len(&self) -> usize {
-        use OpCode::*;
-        match self {
-            Add | Mul | LessThan | Equals => 3,
-            Input | Output | AdjustRbo => 1,
-            JumpIfTrue | JumpIfFalse => 2,
-        }
-    }
-}
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum ParameterMode {
-    Positional,
-    Immediate,
-    Relative,
-}
-
-impl ParameterMode {
-    fn decode(input: &mut Value) -> Self {
-        use ParameterMode::*;
-        let res = match *input % 10 {
-            0 => Positional,
-            1 => Immediate,
-            2 => Relative,
-            other => panic!("Unknown parameter mode: {}", other),
-        };
-        *input /= 10;
-        res
-    }
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Parameter {
-    value: Value,
-    mode: ParameterMode,
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Op {
-    code: OpCode,
-    args: [Parameter; 4],
-}
+use aoc2019::adventure::{Command, Response, Session, Solver};
+use aoc2019::intcode::{self, Value};
 
-#[derive(Debug)]
-struct Program<T: Io> {
-    memory: Vec<Value>,
-    pc: usize,
-    rbo: Value,
-    io: T,
-}
+const INPUT: &str = include_str!("../../inputs/day25.txt");
 
-impl<T: Io> Program<T> {
-    fn new(memory: Vec<Value>, io: T) -> Self {
-        Program {
-            memory,
-            pc: 0,
-            rbo: 0,
-            io,
-        }
-    }
-    fn read_and_advance(&mut self) -> Value {
-        let res = self.memory[self.pc];
-        self.pc += 1;
-        res
-    }
-    fn decode_op(&mut self) -> Op {
-        let mut value = self.read_and_advance();
-        let op_code = OpCode::decode(&mut value);
-        let mut op = Op {
-            code: op_code,
-            args: [Parameter {
-                value: 0,
-                mode: ParameterMode::Positional,
-            }; 4],
-        };
-        for i in 0..op_code.len() {
-            let param = self.read_and_advance();
-            let mode = ParameterMode::decode(&mut value);
-            op.args[i] = Parameter {
-                value: param,
-                mode,
-            };
-        }
-        op
-    }
-    fn read_memory(&self, addr: Value) -> Value {
-        if addr < 0 { panic!("Read from negative address: {}", addr) }
-        let addr = addr as usize;
-        if addr >= self.memory.len() {
-            0
-        } else {
-            self.memory[addr]
-        }
-    }
-    fn write_memory(&mut self, addr: Value, value: Value) {
-        if addr < 0 { panic!("Write to negative address: {}", addr) }
-        let addr = addr as usize;
-        if addr >= self.memory.len() {
-            self.memory.resize(addr+1, 0);
-        }
-        self.memory[addr] = value;
-    }
-    fn read_param(&self, param: Parameter) -> Value {
-        match param.mode {
-            ParameterMode::Positional => self.read_memory(param.value),
-            ParameterMode::Immediate => param.value,
-            ParameterMode::Relative => self.read_memory(param.value + self.rbo),
-        }
-    }
-    fn write_param(&mut self, param: Parameter, v: Value) {
-        match param.mode {
-            ParameterMode::Positional => self.write_memory(param.value, v),
-            ParameterMode::Immediate => panic!("Cannot write to immedaite parameter"),
-            ParameterMode::Relative => self.write_memory(param.value + self.rbo, v),
-        }
-    }
-    fn execute(&mut self, op: Op) -> bool {
-        match op.code {
-            OpCode::Add => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                self.write_param(op.args[2], a+b);
-            },
-            OpCode::Mul => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                self.write_param(op.args[2], a*b);
-            },
-            OpCode::Input => {
-                if let Some(a) = self.io.input() {
-                    self.write_param(op.args[0], a);
-                } else {
-                    return true;
-                }
-            },
-            OpCode::Output => {
-                let a = self.read_param(op.args[0]);
-                self.io.output(a);
-            },
-            OpCode::JumpIfTrue => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                if a != 0 {
-                    self.pc = b as usize;
-                }
-            },
-            OpCode::JumpIfFalse => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                if a == 0 {
-                    self.pc = b as usize;
-                }
-            },
-            OpCode::LessThan => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                self.write_param(op.args[2], if a < b { 1 } else { 0 });
-            },
-            OpCode::Equals => {
-                let a = self.read_param(op.args[0]);
-                let b = self.read_param(op.args[1]);
-                self.write_param(op.args[2], if a == b { 1 } else { 0 });
+// Besides the droid's own commands, `map` prints the ship discovered so far
+// and `dot <file>` saves it as a Graphviz file.
+fn play(memory: Vec<Value>) {
+    let mut session = Session::new(memory);
+    print!("{}", session.start().text());
+
+    for line in io::stdin().lock().lines() {
+        let line = line.unwrap();
+        let line = line.trim();
+        if line == "map" {
+            print!("{}\nCommand?\n", session.graph);
+            continue;
+        }
+        if let Some(path) = line.strip_prefix("dot ") {
+            fs::write(path.trim(), session.graph.to_dot()).unwrap();
+            println!("Saved map to {}\n\nCommand?", path.trim());
+            continue;
+        }
+        let command: Command = match line.parse() {
+            Ok(command) => command,
+            Err(e) => {
+                println!("{}\n\nCommand?", e);
+                continue;
             },
-            OpCode::AdjustRbo => {
-                let a = self.read_param(op.args[0]);
-                self.rbo += a;
-            },
-        }
-        false
-    }
-    fn run(&mut self) {
-        while self.memory[self.pc] != 99 {
-            let op = self.decode_op();
-            if self.execute(op) {
+        };
+        match session.send(&command) {
+            Response::Prompt(s) => print!("{}", s),
+            other => {
+                print!("{}", other.text());
                 break;
-            }
+            },
         }
     }
 }
 
-#[derive(Debug)]
-struct IoState {
-    input: Vec<Value>,
-}
-
-impl IoState {
-    fn new() -> Self {
-        IoState {
-            input: Vec::new(),
-        }
-    }
-}
+fn main() {
+    let memory = intcode::parse(INPUT);
 
-impl Io for IoState {
-    fn input(&mut self) -> Option<Value> {
-        while self.input.is_empty() {
-            let mut s = String::new();
-            io::stdin().read_line(&mut s).unwrap();
-            self.input = s.chars().rev().filter(|&c| c != '\r').map(|c| c as u8 as Value).collect();
-        }
-        self.input.pop()
+    if std::env::args().any(|arg| arg == "--manual") {
+        play(memory);
+        return;
     }
-    fn output(&mut self, value: Value) {
-        print!("{}", value as u8 as char);
-    }
-}
 
-fn main() {
-    let memory: Vec<Value> = INPUT.trim().split(",")
-        .map(|n| n.parse().unwrap())
-        .collect();
-    let mut program = Program::new(memory, IoState::new());
-    program.run();
+    let mut solver = Solver::new(memory);
+    let password = solver.solve();
+    let mut traps: Vec<_> = solver.traps.iter().map(String::as_str).collect();
+    traps.sort();
+    println!("Carrying: {}", solver.session.inventory.join(", "));
+    println!("Traps: {}", traps.join(", "));
+    println!("Commands: {}", solver.session.log.len());
+    println!("{}", password.expect("Failed to get past the security checkpoint"));
 }
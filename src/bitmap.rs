@@ -0,0 +1,62 @@
+use std::fmt;
+
+// A monochrome image, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<bool>,
+}
+
+impl Bitmap {
+    pub fn new(width: usize, height: usize) -> Self {
+        Bitmap {
+            width,
+            height,
+            pixels: vec![false; width*height],
+        }
+    }
+    // The smallest bitmap containing all of the given points.
+    pub fn from_points(points: impl IntoIterator<Item=(i64, i64)>) -> Self {
+        let points: Vec<_> = points.into_iter().collect();
+        let min_x = points.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let max_x = points.iter().map(|&(x, _)| x).max().unwrap_or(-1);
+        let min_y = points.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        let max_y = points.iter().map(|&(_, y)| y).max().unwrap_or(-1);
+
+        let mut bitmap = Bitmap::new((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize);
+        for (x, y) in points {
+            bitmap.set((x - min_x) as usize, (y - min_y) as usize, true);
+        }
+        bitmap
+    }
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.pixels[y*self.width + x]
+    }
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        self.pixels[y*self.width + x] = value;
+    }
+    pub fn rows(&self) -> impl Iterator<Item=&[bool]> {
+        self.pixels.chunks(self.width.max(1))
+    }
+    // A copy of the rectangle with its top-left corner at (x, y).
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        let mut res = Bitmap::new(width, height);
+        for dy in 0..height {
+            for dx in 0..width {
+                res.set(dx, dy, self.get(x + dx, y + dy));
+            }
+        }
+        res
+    }
+}
+
+impl fmt::Display for Bitmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            let line: String = row.iter().map(|&p| if p { '\u{2588}' } else { ' ' }).collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::network::{Packet, NAT_ADDRESS};
+
+// Everything recorded during a network simulation.
+//
+// The file format is line based: a `machines <count>` header, then one
+// `P <tick> <src> <dst> <x> <y>` line per packet and one
+// `Q <tick> <depth>...` line per tick giving each machine's queue depth.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capture {
+    pub count: usize,
+    pub packets: Vec<Packet>,
+    // Queue depths of each machine at the end of each tick.
+    pub depths: Vec<Vec<usize>>,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Filter {
+    pub src: Option<usize>,
+    pub dst: Option<usize>,
+    pub from_tick: Option<usize>,
+    pub to_tick: Option<usize>,
+}
+
+impl Filter {
+    pub fn matches(&self, packet: &Packet) -> bool {
+        self.src.is_none_or(|src| packet.src == src)
+            && self.dst.is_none_or(|dst| packet.dst == dst)
+            && self.from_tick.is_none_or(|tick| packet.tick >= tick)
+            && self.to_tick.is_none_or(|tick| packet.tick <= tick)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    pub packets: usize,
+    pub ticks: usize,
+    pub sent: BTreeMap<usize, usize>,
+    pub received: BTreeMap<usize, usize>,
+    pub nat_wakes: usize,
+    pub max_depth: usize,
+}
+
+// A packet sent by the NAT to wake the network, along with the packets it
+// received since the previous wake-up.
+#[derive(Debug, Clone)]
+pub struct Wake {
+    pub received: Vec<Packet>,
+    pub packet: Packet,
+    pub repeat: bool,
+}
+
+impl Capture {
+    pub fn filter<'a>(&'a self, filter: &'a Filter) -> impl Iterator<Item=&'a Packet> + 'a {
+        self.packets.iter().filter(move |p| filter.matches(p))
+    }
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary {
+            packets: self.packets.len(),
+            ticks: self.depths.len(),
+            max_depth: self.depths.iter().flatten().copied().max().unwrap_or(0),
+            ..Summary::default()
+        };
+        for packet in &self.packets {
+            *summary.sent.entry(packet.src).or_default() += 1;
+            *summary.received.entry(packet.dst).or_default() += 1;
+            if packet.src == NAT_ADDRESS {
+                summary.nat_wakes += 1;
+            }
+        }
+        summary
+    }
+    pub fn nat_wakes(&self) -> Vec<Wake> {
+        let mut wakes: Vec<Wake> = Vec::new();
+        let mut received = Vec::new();
+        for packet in &self.packets {
+            if packet.dst == NAT_ADDRESS {
+                received.push(*packet);
+            } else if packet.src == NAT_ADDRESS {
+                let repeat = wakes.last().is_some_and(|w| w.packet.y == packet.y);
+                wakes.push(Wake {
+                    received: std::mem::take(&mut received),
+                    packet: *packet,
+                    repeat,
+                });
+            }
+        }
+        wakes
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl fmt::Display for Capture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "machines {}", self.count)?;
+        let mut packets = self.packets.iter().peekable();
+        for (tick, depths) in self.depths.iter().enumerate() {
+            while let Some(p) = packets.next_if(|p| p.tick <= tick) {
+                writeln!(f, "P {} {} {} {} {}", p.tick, p.src, p.dst, p.x, p.y)?;
+            }
+            write!(f, "Q {}", tick)?;
+            for depth in depths {
+                write!(f, " {}", depth)?;
+            }
+            writeln!(f)?;
+        }
+        for p in packets {
+            writeln!(f, "P {} {} {} {} {}", p.tick, p.src, p.dst, p.x, p.y)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Capture {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut capture = Capture::default();
+        for (i, line) in s.lines().enumerate() {
+            let mut parts = line.split_whitespace();
+            let kind = match parts.next() {
+                Some(kind) => kind,
+                None => continue,
+            };
+            let values: Vec<i64> = parts
+                .map(|v| v.parse().map_err(|e| format!("Line {}: {}", i + 1, e)))
+                .collect::<Result<_, _>>()?;
+            match (kind, values.as_slice()) {
+                ("machines", &[count]) => capture.count = count as usize,
+                ("P", &[tick, src, dst, x, y]) => capture.packets.push(Packet {
+                    tick: tick as usize,
+                    src: src as usize,
+                    dst: dst as usize,
+                    x,
+                    y,
+                }),
+                ("Q", [_, depths @ ..]) => {
+                    capture.depths.push(depths.iter().map(|&d| d as usize).collect())
+                },
+                _ => return Err(format!("Line {}: malformed record: {:?}", i + 1, line)),
+            }
+        }
+        Ok(capture)
+    }
+}
@@ -0,0 +1,193 @@
+// The Flawed Frequency Transmission. Each phase replaces digit `i` of the
+// signal with the last digit of a sum over the digits from `i` onwards,
+// weighted by the pattern 0, 1, 0, -1 with each element repeated `i + 1`
+// times (skipping the very first). Since no output depends on the digits
+// before it, any window can be computed from the tail of the signal starting
+// at the window.
+
+pub fn parse(s: &str) -> Vec<u8> {
+    s.chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| d as u8)
+        .collect()
+}
+
+pub fn to_string(digits: &[u8]) -> String {
+    digits.iter().map(|&d| char::from(b'0' + d)).collect()
+}
+
+// Runs one phase over `tail`, the digits of a signal from position `start`
+// to the end. Each output sums blocks of the input using prefix sums, and
+// position `i` has about `n / (i + 1)` blocks, so a phase takes O(n log n).
+pub fn phase_from(tail: &[u8], start: usize) -> Vec<u8> {
+    let n = tail.len();
+    let mut prefix = vec![0i64; n + 1];
+    for (k, &d) in tail.iter().enumerate() {
+        prefix[k + 1] = prefix[k] + i64::from(d);
+    }
+    // The sum of `tail[a..b]`, clamping both to the end of the signal.
+    let sum = |a: usize, b: usize| prefix[b.min(n)] - prefix[a.min(n)];
+
+    (0..n).map(|k| {
+        let r = start + k + 1;
+        let mut total = 0;
+        // Blocks of `r` ones start at `k`, and blocks of minus ones `2r`
+        // later, every `4r` digits.
+        let mut a = k;
+        while a < n {
+            total += sum(a, a + r);
+            total -= sum(a + 2*r, a + 3*r);
+            a += 4*r;
+        }
+        (total.abs() % 10) as u8
+    }).collect()
+}
+
+pub fn phase(signal: &[u8]) -> Vec<u8> {
+    phase_from(signal, 0)
+}
+
+// Runs one phase in place over a tail starting in the second half of its
+// signal. There the pattern is zero before each position and one from it to
+// the end, so each output is just the sum of the digits from it onwards.
+pub fn suffix_phase(tail: &mut [u8]) {
+    let mut sum = 0;
+    for d in tail.iter_mut().rev() {
+        sum = (sum + *d) % 10;
+        *d = sum;
+    }
+}
+
+// The `len` digits at `offset` after `phases` phases, for the signal made by
+// repeating `signal` `repeat` times.
+pub fn window(signal: &[u8], repeat: usize, phases: usize, offset: usize, len: usize) -> Vec<u8> {
+    let total = signal.len()*repeat;
+    let mut tail: Vec<u8> = signal.iter().copied().cycle().take(total).skip(offset).collect();
+    if offset >= total / 2 {
+        for _ in 0..phases {
+            suffix_phase(&mut tail);
+        }
+    } else {
+        for _ in 0..phases {
+            tail = phase_from(&tail, offset);
+        }
+    }
+    tail.truncate(len);
+    tail
+}
+
+// `n` choose `k` mod a small prime `p`, by Lucas' theorem: the product of
+// the binomials of the base `p` digits of `n` and `k`.
+fn binomial_mod_prime(mut n: usize, mut k: usize, p: usize) -> usize {
+    let mut res = 1;
+    while k > 0 {
+        let (ni, ki) = (n % p, k % p);
+        if ki > ni {
+            return 0;
+        }
+        // The digits are below `p`, so this never overflows.
+        let mut c = 1;
+        for j in 0..ki {
+            c = c*(ni - j) / (j + 1);
+        }
+        res = res*(c % p) % p;
+        n /= p;
+        k /= p;
+    }
+    res
+}
+
+// `n` choose `k` mod 10, combining the results mod 2 and mod 5: 5 is 1 mod 2
+// and 0 mod 5, and 6 is 0 mod 2 and 1 mod 5.
+pub fn binomial_mod10(n: usize, k: usize) -> u8 {
+    let a = binomial_mod_prime(n, k, 2);
+    let b = binomial_mod_prime(n, k, 5);
+    ((5*a + 6*b) % 10) as u8
+}
+
+// Like `window`, but computing each digit directly rather than phase by
+// phase. In the second half of the signal, digit `i` after `p` phases is
+// the sum of the digits `j` from `i` onwards weighted by `p - 1 + j - i`
+// choose `j - i`. Returns `None` for windows starting in the first half.
+pub fn closed_form_window(signal: &[u8], repeat: usize, phases: usize, offset: usize, len: usize) -> Option<Vec<u8>> {
+    let total = signal.len()*repeat;
+    if offset < total / 2 {
+        return None;
+    }
+    let digit = |i: usize| usize::from(signal[i % signal.len()]);
+    let end = total.min(offset + len);
+    if phases == 0 {
+        return Some((offset..end).map(|i| digit(i) as u8).collect());
+    }
+    let coefficients: Vec<usize> = (0..total - offset)
+        .map(|d| usize::from(binomial_mod10(phases - 1 + d, d)))
+        .collect();
+    Some((offset..end).map(|i| {
+        let sum: usize = (i..total).zip(&coefficients).map(|(j, &c)| c*digit(j)).sum();
+        (sum % 10) as u8
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases_of_example_signal() {
+        let mut signal = parse("12345678");
+        let expected = ["48226158", "34040438", "03415518", "01029498"];
+        for &digits in &expected {
+            signal = phase(&signal);
+            assert_eq!(to_string(&signal), digits);
+        }
+    }
+
+    #[test]
+    fn binomial_mod10_matches_pascals_triangle() {
+        let mut row = vec![1u8];
+        for n in 0..60 {
+            for (k, &c) in row.iter().enumerate() {
+                assert_eq!(binomial_mod10(n, k), c, "{} choose {}", n, k);
+            }
+            assert_eq!(binomial_mod10(n, n + 1), 0);
+            let mut next = vec![1u8; n + 2];
+            for k in 1..=n {
+                next[k] = (row[k - 1] + row[k]) % 10;
+            }
+            row = next;
+        }
+    }
+
+    #[test]
+    fn closed_form_matches_phases() {
+        let signal = parse("80871224585914546619083218645595");
+        let repeat = 3;
+        let total = signal.len()*repeat;
+        for offset in (total / 2..total).step_by(7) {
+            for &phases in &[0, 1, 2, 5, 17, 100] {
+                assert_eq!(
+                    closed_form_window(&signal, repeat, phases, offset, 8),
+                    Some(window(&signal, repeat, phases, offset, 8)),
+                    "offset {}, {} phases", offset, phases
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn closed_form_only_covers_second_half() {
+        let signal = parse("12345678");
+        assert_eq!(closed_form_window(&signal, 2, 4, 7, 1), None);
+        assert_eq!(closed_form_window(&signal, 2, 4, 8, 1), Some(window(&signal, 2, 4, 8, 1)));
+    }
+
+    #[test]
+    fn window_in_first_half_matches_full_phases() {
+        let signal = parse("03036732577212944063491565474664");
+        let mut full: Vec<u8> = signal.iter().copied().cycle().take(signal.len()*2).collect();
+        for _ in 0..10 {
+            full = phase(&full);
+        }
+        assert_eq!(window(&signal, 2, 10, 5, 8), full[5..13].to_vec());
+    }
+}
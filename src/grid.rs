@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+
+// Positions are (x, y) with y increasing downwards, as in every puzzle map.
+pub type Point = (i64, i64);
+
+// The four compass directions, numbered as in the Intcode movement commands.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    North = 1,
+    South = 2,
+    West = 3,
+    East = 4,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::South, Direction::West, Direction::East];
+
+    pub fn rev(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::East => Direction::West,
+        }
+    }
+    pub fn left(self) -> Self {
+        match self {
+            Direction::North => Direction::West,
+            Direction::West => Direction::South,
+            Direction::South => Direction::East,
+            Direction::East => Direction::North,
+        }
+    }
+    pub fn right(self) -> Self {
+        self.left().rev()
+    }
+    pub fn offset(self) -> Point {
+        match self {
+            Direction::North => (0, -1),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+            Direction::East => (1, 0),
+        }
+    }
+    pub fn from_offset(offset: Point) -> Option<Self> {
+        Direction::ALL.iter().copied().find(|dir| dir.offset() == offset)
+    }
+    pub fn apply(self, other: Point) -> Point {
+        let (dx, dy) = self.offset();
+        (other.0 + dx, other.1 + dy)
+    }
+    // The Intcode movement command for this direction.
+    pub fn code(self) -> i64 {
+        self as i64
+    }
+    // How a robot facing this way is drawn.
+    pub fn arrow(self) -> char {
+        match self {
+            Direction::North => '^',
+            Direction::South => 'v',
+            Direction::West => '<',
+            Direction::East => '>',
+        }
+    }
+    pub fn from_arrow(c: char) -> Option<Self> {
+        Direction::ALL.iter().copied().find(|dir| dir.arrow() == c)
+    }
+}
+
+impl From<i64> for Direction {
+    fn from(other: i64) -> Self {
+        match other {
+            1 => Direction::North,
+            2 => Direction::South,
+            3 => Direction::West,
+            4 => Direction::East,
+            _ => panic!("Unknown direction: {}", other),
+        }
+    }
+}
+
+// The four orthogonally adjacent points, with the direction to each.
+pub fn neighbours(pos: Point) -> impl Iterator<Item=(Direction, Point)> {
+    Direction::ALL.iter().map(move |&dir| (dir, dir.apply(pos)))
+}
+
+pub fn manhattan(a: Point, b: Point) -> i64 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs()
+}
+
+// Returns (min_x, min_y, max_x, max_y), or all zeroes if there are no points.
+pub fn bounds(points: impl IntoIterator<Item=Point>) -> (i64, i64, i64, i64) {
+    let mut res: Option<(i64, i64, i64, i64)> = None;
+    for (x, y) in points {
+        res = Some(match res {
+            Some((min_x, min_y, max_x, max_y)) => (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+            None => (x, y, x, y),
+        });
+    }
+    res.unwrap_or((0, 0, 0, 0))
+}
+
+// A rectangular map with its top-left corner at the origin. Points outside it
+// are simply absent, so walking off the edge never wraps or underflows.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self where T: Clone {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width*height],
+        }
+    }
+    // Parses an ASCII map, one row per line. Short lines are padded with
+    // whatever `' '` parses as.
+    pub fn parse<E>(s: &str, mut cell: impl FnMut(Point, char) -> Result<T, E>) -> Result<Self, E> {
+        let lines: Vec<&str> = s.lines().collect();
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        let mut cells = Vec::with_capacity(width*lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            let mut chars = line.chars();
+            for x in 0..width {
+                cells.push(cell((x as i64, y as i64), chars.next().unwrap_or(' '))?);
+            }
+        }
+        Ok(Grid {
+            width,
+            height: lines.len(),
+            cells,
+        })
+    }
+    fn index(&self, (x, y): Point) -> Option<usize> {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            Some(y as usize*self.width + x as usize)
+        } else {
+            None
+        }
+    }
+    pub fn contains(&self, pos: Point) -> bool {
+        self.index(pos).is_some()
+    }
+    pub fn get(&self, pos: Point) -> Option<&T> {
+        self.index(pos).map(|i| &self.cells[i])
+    }
+    pub fn get_mut(&mut self, pos: Point) -> Option<&mut T> {
+        self.index(pos).map(move |i| &mut self.cells[i])
+    }
+    // Panics if `pos` is outside the grid.
+    pub fn set(&mut self, pos: Point, value: T) {
+        let i = self.index(pos).unwrap_or_else(|| panic!("{:?} is outside the grid", pos));
+        self.cells[i] = value;
+    }
+    pub fn points(&self) -> impl Iterator<Item=Point> {
+        let width = self.width as i64;
+        (0..self.height as i64).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+    pub fn iter(&self) -> impl Iterator<Item=(Point, &T)> {
+        self.points().zip(&self.cells)
+    }
+    pub fn find(&self, mut pred: impl FnMut(&T) -> bool) -> Option<Point> {
+        self.iter().find(|(_, cell)| pred(cell)).map(|(pos, _)| pos)
+    }
+    // The neighbours of `pos` which are inside the grid.
+    pub fn neighbours(&self, pos: Point) -> impl Iterator<Item=(Direction, Point, &T)> {
+        neighbours(pos).filter_map(move |(dir, new_pos)| self.get(new_pos).map(|cell| (dir, new_pos, cell)))
+    }
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+    pub fn render(&self, mut glyph: impl FnMut(&T) -> char) -> String {
+        let mut s = String::new();
+        for row in self.cells.chunks(self.width.max(1)) {
+            s.extend(row.iter().map(&mut glyph));
+            s.push('\n');
+        }
+        s
+    }
+}
+
+// A map without fixed bounds, such as one being discovered by a robot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        SparseGrid {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        SparseGrid::default()
+    }
+    // Parses an ASCII map, one row per line, skipping any character for
+    // which `cell` returns `None`.
+    pub fn parse<E>(s: &str, mut cell: impl FnMut(Point, char) -> Result<Option<T>, E>) -> Result<Self, E> {
+        let mut grid = SparseGrid::new();
+        for (y, line) in s.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let pos = (x as i64, y as i64);
+                if let Some(value) = cell(pos, c)? {
+                    grid.insert(pos, value);
+                }
+            }
+        }
+        Ok(grid)
+    }
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+    pub fn contains(&self, pos: Point) -> bool {
+        self.cells.contains_key(&pos)
+    }
+    pub fn get(&self, pos: Point) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+    pub fn get_mut(&mut self, pos: Point) -> Option<&mut T> {
+        self.cells.get_mut(&pos)
+    }
+    pub fn insert(&mut self, pos: Point, value: T) -> Option<T> {
+        self.cells.insert(pos, value)
+    }
+    pub fn remove(&mut self, pos: Point) -> Option<T> {
+        self.cells.remove(&pos)
+    }
+    pub fn iter(&self) -> impl Iterator<Item=(Point, &T)> {
+        self.cells.iter().map(|(&pos, cell)| (pos, cell))
+    }
+    pub fn find(&self, mut pred: impl FnMut(&T) -> bool) -> Option<Point> {
+        self.iter().find(|(_, cell)| pred(cell)).map(|(pos, _)| pos)
+    }
+    // The neighbours of `pos` which are present.
+    pub fn neighbours(&self, pos: Point) -> impl Iterator<Item=(Direction, Point, &T)> {
+        neighbours(pos).filter_map(move |(dir, new_pos)| self.get(new_pos).map(|cell| (dir, new_pos, cell)))
+    }
+    // Returns (min_x, min_y, max_x, max_y) of the cells present.
+    pub fn bounds(&self) -> (i64, i64, i64, i64) {
+        bounds(self.cells.keys().copied())
+    }
+    // Renders the bounding box of the grid, with spaces for absent cells and
+    // trailing spaces trimmed.
+    pub fn render(&self, mut glyph: impl FnMut(Point, Option<&T>) -> char) -> String {
+        let (min_x, min_y, max_x, max_y) = self.bounds();
+        let mut s = String::new();
+        if self.is_empty() {
+            return s;
+        }
+        for y in min_y..=max_y {
+            let line: String = (min_x..=max_x).map(|x| glyph((x, y), self.get((x, y)))).collect();
+            s += line.trim_end();
+            s.push('\n');
+        }
+        s
+    }
+    // Copies the cells into a bounded grid, shifting them so the top-left
+    // corner of the bounding box is at the origin. Returns the grid and the
+    // offset which was subtracted.
+    pub fn to_grid(&self, fill: T) -> (Grid<T>, Point) where T: Clone {
+        let (min_x, min_y, max_x, max_y) = self.bounds();
+        let mut grid = if self.is_empty() {
+            Grid::new(0, 0, fill)
+        } else {
+            Grid::new((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize, fill)
+        };
+        for (&(x, y), cell) in &self.cells {
+            grid.set((x - min_x, y - min_y), cell.clone());
+        }
+        (grid, (min_x, min_y))
+    }
+}
+
+impl<T> FromIterator<(Point, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item=(Point, T)>>(iter: I) -> Self {
+        SparseGrid {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(|&c| c))
+    }
+}
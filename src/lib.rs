@@ -0,0 +1,20 @@
+pub mod adventure;
+pub mod bitmap;
+pub mod capture;
+pub mod fft;
+pub mod grid;
+pub mod intcode;
+pub mod nanofactory;
+pub mod nbody;
+pub mod network;
+pub mod ocr;
+pub mod painting_robot;
+pub mod pnm;
+pub mod portal_maze;
+pub mod record;
+pub mod render;
+pub mod repair_droid;
+pub mod search;
+pub mod sif;
+pub mod tractor_beam;
+pub mod vault;
@@ -0,0 +1,437 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+pub const ORE: &str = "ORE";
+pub const FUEL: &str = "FUEL";
+
+// Above this many combinations of alternative reactions, plans are improved
+// one choice at a time instead of trying every combination.
+const MAX_COMBINATIONS: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub chemical: String,
+    pub amount: u64,
+}
+
+impl Component {
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut parts = s.trim().splitn(2, ' ');
+        let amount = parts.next().unwrap_or("");
+        let chemical = parts.next().ok_or_else(|| format!("Expected amount and chemical in {:?}", s))?;
+        Ok(Component {
+            amount: amount.parse().map_err(|e| format!("Invalid amount {:?}: {}", amount, e))?,
+            chemical: chemical.trim().into(),
+        })
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.chemical)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reaction {
+    pub inputs: Vec<Component>,
+    pub output: Component,
+}
+
+impl Reaction {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut sides = line.splitn(2, "=>");
+        let lhs = sides.next().unwrap_or("");
+        let rhs = sides.next().ok_or_else(|| format!("Expected \"=>\" in {:?}", line))?;
+        Ok(Reaction {
+            inputs: lhs.split(',').map(Component::parse).collect::<Result<_, _>>()?,
+            output: Component::parse(rhs)?,
+        })
+    }
+}
+
+impl fmt::Display for Reaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, input) in self.inputs.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", input)?;
+        }
+        write!(f, " => {}", self.output)
+    }
+}
+
+// How much of each chemical a plan needs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    // The total cost of the raw materials used.
+    pub cost: u64,
+    // How much of each raw material is used.
+    pub raw: BTreeMap<String, u64>,
+    // How many times each reaction runs, by output chemical.
+    pub runs: BTreeMap<String, u64>,
+    // The reaction chosen for each chemical which is made.
+    pub reactions: BTreeMap<String, Reaction>,
+    // By-products left over once the target has been made, by chemical.
+    pub leftovers: BTreeMap<String, u64>,
+    // Whether the plan is known to be the cheapest. Plans found by switching
+    // one choice at a time may not be.
+    pub exact: bool,
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (chemical, amount) in &self.raw {
+            writeln!(f, "{} {}", amount, chemical)?;
+        }
+        for (chemical, runs) in &self.runs {
+            write!(f, "{} x{}", self.reactions[chemical], runs)?;
+            match self.leftovers.get(chemical) {
+                Some(left) => writeln!(f, ", {} left over", left)?,
+                None => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// A set of reactions which turn raw materials into everything else. Each raw
+// material has a cost per unit, and a chemical may have several reactions
+// producing it, in which case the cheapest combination is chosen.
+#[derive(Debug, Clone)]
+pub struct Nanofactory {
+    reactions: HashMap<String, Vec<Reaction>>,
+    raw: BTreeMap<String, u64>,
+    // Every chemical ordered so that each comes before all of its inputs.
+    order: Vec<String>,
+}
+
+impl Nanofactory {
+    // Parses one reaction per line, reporting problems by line number. ORE is
+    // the only raw material, and each chemical has exactly one reaction.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut raw = BTreeMap::new();
+        raw.insert(ORE.into(), 1);
+        Nanofactory::parse_with(input, raw, false)
+    }
+    // Parses reactions made from the given raw materials, allowing any number
+    // of reactions to produce the same chemical.
+    pub fn parse_with_raw(input: &str, raw: BTreeMap<String, u64>) -> Result<Self, String> {
+        Nanofactory::parse_with(input, raw, true)
+    }
+    fn parse_with(input: &str, raw: BTreeMap<String, u64>, alternatives: bool) -> Result<Self, String> {
+        let mut reactions = Vec::new();
+        let mut lines = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reaction = Reaction::parse(line).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+            reactions.push(reaction);
+            lines.push(i + 1);
+        }
+        Nanofactory::build(reactions, raw, alternatives, |i| format!("Line {}", lines[i]))
+    }
+    pub fn new(reactions: impl IntoIterator<Item=Reaction>, raw: BTreeMap<String, u64>) -> Result<Self, String> {
+        Nanofactory::build(reactions.into_iter().collect(), raw, true, |i| format!("Reaction {}", i + 1))
+    }
+    // Checks every amount is positive, every chemical other than the raw
+    // materials is produced by some reaction, by exactly one unless
+    // `alternatives` is set, and that no chemical can be needed to make
+    // itself. `locate` describes where the reaction with a given index came
+    // from.
+    fn build(
+        reactions: Vec<Reaction>,
+        raw: BTreeMap<String, u64>,
+        alternatives: bool,
+        locate: impl Fn(usize) -> String,
+    ) -> Result<Self, String> {
+        let mut producers: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, reaction) in reactions.iter().enumerate() {
+            if let Some(c) = reaction.inputs.iter().chain(Some(&reaction.output)).find(|c| c.amount == 0) {
+                return Err(format!("{}: Amount of {} must be positive", locate(i), c.chemical));
+            }
+            let chemical = &reaction.output.chemical;
+            if raw.contains_key(chemical) {
+                return Err(format!("{}: {} is a raw material and can't be produced", locate(i), chemical));
+            }
+            let indices = producers.entry(chemical.clone()).or_default();
+            if let Some(&j) = indices.first().filter(|_| !alternatives) {
+                return Err(format!("{}: {} is already produced by {}", locate(i), chemical, locate(j).to_lowercase()));
+            }
+            indices.push(i);
+        }
+        for (i, reaction) in reactions.iter().enumerate() {
+            for input in &reaction.inputs {
+                if !raw.contains_key(&input.chemical) && !producers.contains_key(&input.chemical) {
+                    return Err(format!("{}: {} is never produced", locate(i), input.chemical));
+                }
+            }
+        }
+
+        // A depth-first search puts each chemical after all of its inputs,
+        // which reversed is the order in which they need to be worked out.
+        // Meeting a chemical which is still on the stack means a cycle.
+        struct Visit<'a> {
+            reactions: &'a [Reaction],
+            producers: &'a HashMap<String, Vec<usize>>,
+            done: HashSet<&'a str>,
+            stack: Vec<&'a str>,
+            order: Vec<String>,
+        }
+        impl<'a> Visit<'a> {
+            fn visit(&mut self, chemical: &'a str) -> Result<(), Vec<&'a str>> {
+                if self.done.contains(chemical) {
+                    return Ok(());
+                }
+                if let Some(i) = self.stack.iter().position(|&c| c == chemical) {
+                    let mut cycle = self.stack[i..].to_vec();
+                    cycle.push(chemical);
+                    return Err(cycle);
+                }
+                self.stack.push(chemical);
+                for &i in self.producers.get(chemical).into_iter().flatten() {
+                    for input in &self.reactions[i].inputs {
+                        self.visit(&input.chemical)?;
+                    }
+                }
+                self.stack.pop();
+                self.done.insert(chemical);
+                self.order.push(chemical.into());
+                Ok(())
+            }
+        }
+        let mut visit = Visit {
+            reactions: &reactions,
+            producers: &producers,
+            done: HashSet::new(),
+            stack: Vec::new(),
+            order: Vec::new(),
+        };
+        let mut chemicals: Vec<_> = producers.keys().chain(raw.keys()).collect();
+        chemicals.sort();
+        for chemical in chemicals {
+            if let Err(cycle) = visit.visit(chemical) {
+                return Err(format!(
+                    "{}: {} is needed to make itself: {}",
+                    locate(producers[cycle[0]][0]), cycle[0], cycle.join(" <- "),
+                ));
+            }
+        }
+        let mut order = visit.order;
+        order.reverse();
+
+        let mut by_output: HashMap<String, Vec<Reaction>> = HashMap::new();
+        for reaction in reactions {
+            by_output.entry(reaction.output.chemical.clone()).or_default().push(reaction);
+        }
+        Ok(Nanofactory { reactions: by_output, raw, order })
+    }
+    // Every reaction producing `chemical`.
+    pub fn reactions(&self, chemical: &str) -> &[Reaction] {
+        self.reactions.get(chemical).map_or(&[], |r| &r[..])
+    }
+    pub fn raw_cost(&self, chemical: &str) -> Option<u64> {
+        self.raw.get(chemical).copied()
+    }
+    // The reactions as a graph from each input to the chemicals made from it.
+    // Alternative reactions for the same chemical get a node each.
+    pub fn to_dot(&self) -> String {
+        fn quote(s: &str) -> String {
+            format!("\"{}\"", s.replace('"', "\\\""))
+        }
+
+        let mut s = String::from("digraph reactions {\n");
+        for chemical in &self.order {
+            match (self.reactions.get(chemical), self.raw_cost(chemical)) {
+                (Some(reactions), _) if reactions.len() == 1 => {
+                    let reaction = &reactions[0];
+                    s += &format!("    {} [label={}];\n", quote(chemical), quote(&reaction.output.to_string()));
+                    for input in &reaction.inputs {
+                        s += &format!("    {} -> {} [label={}];\n", quote(&input.chemical), quote(chemical), input.amount);
+                    }
+                },
+                (Some(reactions), _) => {
+                    s += &format!("    {};\n", quote(chemical));
+                    for (i, reaction) in reactions.iter().enumerate() {
+                        let node = quote(&format!("{}#{}", chemical, i + 1));
+                        s += &format!("    {} [label={}, shape=diamond];\n", node, quote(&reaction.output.to_string()));
+                        s += &format!("    {} -> {};\n", node, quote(chemical));
+                        for input in &reaction.inputs {
+                            s += &format!("    {} -> {} [label={}];\n", quote(&input.chemical), node, input.amount);
+                        }
+                    }
+                },
+                (None, cost) => {
+                    let label = format!("{}\\n{} each", chemical, cost.unwrap_or(0));
+                    s += &format!("    {} [label={}, shape=box];\n", quote(chemical), quote(&label));
+                },
+            }
+        }
+        s += "}\n";
+        s
+    }
+    // Works out how to make `amount` of `chemical` using the reaction with
+    // the given index for each chemical. Every consumer of a chemical is dealt
+    // with before the chemical itself, so each reaction only needs to be
+    // considered once.
+    fn plan_with(&self, chemical: &str, amount: u64, choices: &HashMap<&str, usize>) -> Plan {
+        let mut needed: HashMap<&str, u64> = HashMap::new();
+        needed.insert(chemical, amount);
+        let mut plan = Plan::default();
+        for chemical in &self.order {
+            let want = needed.get(chemical.as_str()).copied().unwrap_or(0);
+            if want == 0 {
+                continue;
+            }
+            if let Some(&cost) = self.raw.get(chemical) {
+                plan.raw.insert(chemical.clone(), want);
+                plan.cost = plan.cost.saturating_add(cost.saturating_mul(want));
+                continue;
+            }
+            let reaction = &self.reactions[chemical][choices.get(chemical.as_str()).copied().unwrap_or(0)];
+            let runs = want / reaction.output.amount + u64::from(want % reaction.output.amount != 0);
+            plan.runs.insert(chemical.clone(), runs);
+            plan.reactions.insert(chemical.clone(), reaction.clone());
+            let made = reaction.output.amount.saturating_mul(runs);
+            if made > want {
+                plan.leftovers.insert(chemical.clone(), made - want);
+            }
+            for input in &reaction.inputs {
+                let total = needed.entry(&input.chemical).or_insert(0);
+                *total = total.saturating_add(input.amount.saturating_mul(runs));
+            }
+        }
+        plan
+    }
+    // Picks the reaction for each chemical with the lowest cost per unit,
+    // ignoring leftovers, working from the raw materials up.
+    fn cheapest_choices(&self) -> HashMap<&str, usize> {
+        let mut unit_costs: HashMap<&str, f64> = HashMap::new();
+        let mut choices = HashMap::new();
+        for chemical in self.order.iter().rev() {
+            if let Some(&cost) = self.raw.get(chemical) {
+                unit_costs.insert(chemical, cost as f64);
+                continue;
+            }
+            let (choice, cost) = self.reactions[chemical].iter()
+                .map(|reaction| {
+                    let inputs: f64 = reaction.inputs.iter()
+                        .map(|input| input.amount as f64 * unit_costs[input.chemical.as_str()])
+                        .sum();
+                    inputs / reaction.output.amount as f64
+                })
+                .enumerate()
+                .fold((0, f64::INFINITY), |best, (i, cost)| if cost < best.1 { (i, cost) } else { best });
+            unit_costs.insert(chemical, cost);
+            choices.insert(chemical.as_str(), choice);
+        }
+        choices
+    }
+    // Every chemical which some way of making `chemical` might use,
+    // including itself.
+    fn reachable<'a>(&'a self, chemical: &'a str) -> HashSet<&'a str> {
+        let mut res = HashSet::new();
+        let mut stack = vec![chemical];
+        while let Some(c) = stack.pop() {
+            if res.insert(c) {
+                for reaction in self.reactions(c) {
+                    stack.extend(reaction.inputs.iter().map(|input| input.chemical.as_str()));
+                }
+            }
+        }
+        res
+    }
+    // Works out how to make `amount` of `chemical` as cheaply as possible.
+    // When there are few enough combinations of the alternative reactions it
+    // might use every one is tried. Otherwise, starting from the cheapest
+    // reaction per unit for each chemical, choices are switched one at a time
+    // for as long as that makes the plan cheaper, which can get stuck on a
+    // plan that isn't the cheapest; such plans aren't marked `exact`.
+    pub fn plan(&self, chemical: &str, amount: u64) -> Plan {
+        let mut choices = self.cheapest_choices();
+        let mut best = self.plan_with(chemical, amount, &choices);
+
+        let reachable = self.reachable(chemical);
+        let options: Vec<(&str, usize)> = self.order.iter()
+            .map(|c| (c.as_str(), self.reactions(c).len()))
+            .filter(|&(c, n)| n > 1 && reachable.contains(c))
+            .collect();
+        if options.is_empty() {
+            best.exact = true;
+            return best;
+        }
+
+        let combinations = options.iter().try_fold(1usize, |acc, &(_, n)| acc.checked_mul(n));
+        match combinations {
+            Some(combinations) if combinations <= MAX_COMBINATIONS => {
+                for mut index in 0..combinations {
+                    for &(c, n) in &options {
+                        choices.insert(c, index % n);
+                        index /= n;
+                    }
+                    let plan = self.plan_with(chemical, amount, &choices);
+                    if plan.cost < best.cost {
+                        best = plan;
+                    }
+                }
+                best.exact = true;
+            },
+            _ => {
+                let mut improved = true;
+                while improved {
+                    improved = false;
+                    for &(c, n) in &options {
+                        let mut current = choices[c];
+                        for i in 0..n {
+                            choices.insert(c, i);
+                            let plan = self.plan_with(chemical, amount, &choices);
+                            if plan.cost < best.cost {
+                                best = plan;
+                                current = i;
+                                improved = true;
+                            }
+                        }
+                        choices.insert(c, current);
+                    }
+                }
+            },
+        }
+        best
+    }
+    pub fn cost_for(&self, chemical: &str, amount: u64) -> u64 {
+        self.plan(chemical, amount).cost
+    }
+    // The most of `chemical` which can be made for `budget`. Leftovers mean
+    // the cost per unit falls as the amount grows, so making one unit at a
+    // time gives a lower bound, and doubling from there finds an upper bound
+    // for a binary search. The search relies on the cost never falling as
+    // the amount grows, which holds for exact plans; when `plan` falls back
+    // to its local search the result is only an estimate.
+    pub fn max_output(&self, chemical: &str, budget: u64) -> u64 {
+        let per_unit = self.cost_for(chemical, 1);
+        if per_unit > budget {
+            return 0;
+        }
+        if per_unit == 0 {
+            return u64::MAX;
+        }
+        let mut lo = budget / per_unit;
+        let mut hi = lo.saturating_mul(2);
+        while hi < u64::MAX && self.cost_for(chemical, hi) <= budget {
+            lo = hi;
+            hi = hi.saturating_mul(2);
+        }
+        // cost_for(lo) <= budget < cost_for(hi)
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo)/2;
+            if self.cost_for(chemical, mid) <= budget {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+}
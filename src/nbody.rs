@@ -0,0 +1,136 @@
+// Bodies attracting each other one unit of velocity at a time along each
+// axis. The axes never interact, so each can be simulated on its own.
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Body {
+    pub pos: Vec<i64>,
+    pub vel: Vec<i64>,
+}
+
+impl Body {
+    pub fn new(pos: Vec<i64>) -> Self {
+        let vel = vec![0; pos.len()];
+        Body { pos, vel }
+    }
+    // Parses `<x=1, y=2, z=3>`, with any names and number of coordinates.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let inner = s.trim()
+            .strip_prefix('<')
+            .and_then(|s| s.strip_suffix('>'))
+            .ok_or_else(|| format!("Expected <...> in {:?}", s))?;
+        let pos = inner.split(',').map(|coord| {
+            let (_, value) = coord.split_once('=').ok_or_else(|| format!("Expected name=value in {:?}", coord))?;
+            value.trim().parse().map_err(|e| format!("Invalid coordinate {:?}: {}", value, e))
+        }).collect::<Result<_, String>>()?;
+        Ok(Body::new(pos))
+    }
+    pub fn potential_energy(&self) -> i64 {
+        self.pos.iter().map(|p| p.abs()).sum()
+    }
+    pub fn kinetic_energy(&self) -> i64 {
+        self.vel.iter().map(|v| v.abs()).sum()
+    }
+    pub fn energy(&self) -> i64 {
+        self.potential_energy()*self.kinetic_energy()
+    }
+}
+
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+// The least common multiple, or `None` if it doesn't fit in a u64. Dividing
+// first keeps the intermediate result no larger than the answer.
+pub fn lcm(a: u64, b: u64) -> Option<u64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+// Steps the positions and velocities along one axis, one entry per body.
+fn step_axis(pos: &mut [i64], vel: &mut [i64]) {
+    for i in 0..pos.len() {
+        for j in (i + 1)..pos.len() {
+            let d = (pos[j] - pos[i]).signum();
+            vel[i] += d;
+            vel[j] -= d;
+        }
+    }
+    for (p, v) in pos.iter_mut().zip(vel.iter()) {
+        *p += v;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct System {
+    pub bodies: Vec<Body>,
+    pub dims: usize,
+}
+
+impl System {
+    pub fn new(bodies: Vec<Body>) -> Result<Self, String> {
+        let dims = bodies.first().map_or(0, |body| body.pos.len());
+        if let Some(i) = bodies.iter().position(|body| body.pos.len() != dims || body.vel.len() != dims) {
+            return Err(format!("Body {} doesn't have {} dimensions", i + 1, dims));
+        }
+        Ok(System { bodies, dims })
+    }
+    // Parses one body per line.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let bodies = input.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| Body::parse(line).map_err(|e| format!("Line {}: {}", i + 1, e)))
+            .collect::<Result<_, _>>()?;
+        System::new(bodies)
+    }
+    fn axis(&self, dim: usize) -> (Vec<i64>, Vec<i64>) {
+        self.bodies.iter().map(|body| (body.pos[dim], body.vel[dim])).unzip()
+    }
+    pub fn step(&mut self) {
+        for dim in 0..self.dims {
+            let (mut pos, mut vel) = self.axis(dim);
+            step_axis(&mut pos, &mut vel);
+            for (body, (p, v)) in self.bodies.iter_mut().zip(pos.into_iter().zip(vel)) {
+                body.pos[dim] = p;
+                body.vel[dim] = v;
+            }
+        }
+    }
+    pub fn steps(&mut self, n: usize) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+    pub fn energy(&self) -> i64 {
+        self.bodies.iter().map(Body::energy).sum()
+    }
+    // How many steps until the given axis is back where it started. Each
+    // step can be undone, so the first state to repeat is the initial one.
+    pub fn axis_period(&self, dim: usize) -> u64 {
+        let (mut pos, mut vel) = self.axis(dim);
+        let initial = (pos.clone(), vel.clone());
+        let mut steps = 0;
+        loop {
+            step_axis(&mut pos, &mut vel);
+            steps += 1;
+            if pos == initial.0 && vel == initial.1 {
+                return steps;
+            }
+        }
+    }
+    pub fn axis_periods(&self) -> Vec<u64> {
+        (0..self.dims).map(|dim| self.axis_period(dim)).collect()
+    }
+    // How many steps until the whole system is back where it started, or
+    // `None` if that doesn't fit in a u64.
+    pub fn period(&self) -> Option<u64> {
+        self.axis_periods().into_iter().try_fold(1, lcm)
+    }
+}
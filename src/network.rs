@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+
+use crate::capture::Capture;
+use crate::intcode::{Io, Program, Value};
+
+pub const NAT_ADDRESS: usize = 255;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub src: usize,
+    pub dst: usize,
+    pub x: Value,
+    pub y: Value,
+    pub tick: usize,
+}
+
+// Hook for whatever sits at the NAT address.
+pub trait Nat {
+    // Called for every packet sent to the NAT address.
+    fn receive(&mut self, x: Value, y: Value);
+    // Called when the network is idle. Returns the packet to send to
+    // address 0, or `None` to stop the simulation.
+    fn wake(&mut self) -> Option<(Value, Value)>;
+    // Checked after every packet; returning `true` stops the simulation.
+    fn done(&self) -> bool {
+        false
+    }
+}
+
+// Stops as soon as the first packet reaches the NAT.
+#[derive(Debug, Default)]
+pub struct FirstPacket {
+    pub packet: Option<(Value, Value)>,
+}
+
+impl Nat for FirstPacket {
+    fn receive(&mut self, x: Value, y: Value) {
+        self.packet.get_or_insert((x, y));
+    }
+    fn wake(&mut self) -> Option<(Value, Value)> {
+        None
+    }
+    fn done(&self) -> bool {
+        self.packet.is_some()
+    }
+}
+
+// Wakes the network with the last packet it received, and stops once it
+// sends the same Y value twice in a row.
+#[derive(Debug, Default)]
+pub struct RepeatDetector {
+    last: Option<(Value, Value)>,
+    pub sent: Vec<Value>,
+}
+
+impl RepeatDetector {
+    pub fn repeated(&self) -> Option<Value> {
+        match self.sent.as_slice() {
+            [.., a, b] if a == b => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl Nat for RepeatDetector {
+    fn receive(&mut self, x: Value, y: Value) {
+        self.last = Some((x, y));
+    }
+    fn wake(&mut self) -> Option<(Value, Value)> {
+        let (x, y) = self.last?;
+        self.sent.push(y);
+        Some((x, y))
+    }
+    fn done(&self) -> bool {
+        self.repeated().is_some()
+    }
+}
+
+#[derive(Debug)]
+enum OutputState {
+    Empty,
+    Dest(usize),
+    DestX(usize, Value),
+}
+
+#[derive(Debug)]
+struct Nic {
+    boot: Option<Value>,
+    queue: VecDeque<(Value, Value)>,
+    pending_y: Option<Value>,
+    out_state: OutputState,
+    outgoing: Vec<(usize, Value, Value)>,
+    // Whether the machine has read -1 since it last received or sent anything.
+    idle: bool,
+    // Whether the machine has already read -1 during the current tick.
+    polled: bool,
+}
+
+impl Io for Nic {
+    fn input(&mut self) -> Option<Value> {
+        if let Some(address) = self.boot.take() {
+            Some(address)
+        } else if let Some(y) = self.pending_y.take() {
+            Some(y)
+        } else if let Some((x, y)) = self.queue.pop_front() {
+            self.idle = false;
+            self.pending_y = Some(y);
+            Some(x)
+        } else if !self.polled {
+            self.polled = true;
+            self.idle = true;
+            Some(-1)
+        } else {
+            // Yield to the next machine rather than spinning on -1.
+            None
+        }
+    }
+    fn output(&mut self, value: Value) {
+        self.idle = false;
+        self.out_state = match self.out_state {
+            OutputState::Empty => OutputState::Dest(value as usize),
+            OutputState::Dest(dst) => OutputState::DestX(dst, value),
+            OutputState::DestX(dst, x) => {
+                self.outgoing.push((dst, x, value));
+                OutputState::Empty
+            },
+        };
+    }
+}
+
+// Runs every machine in turn on a single thread, so that the packets
+// exchanged (and hence the NAT's behaviour) are fully deterministic.
+#[derive(Debug)]
+pub struct Network<N: Nat> {
+    machines: Vec<Program<Nic>>,
+    nat: N,
+    tick: usize,
+    log: Option<Vec<Packet>>,
+    depth_log: Vec<Vec<usize>>,
+    stopped: bool,
+}
+
+impl<N: Nat> Network<N> {
+    pub fn new(memory: &[Value], count: usize, nat: N) -> Self {
+        let machines = (0..count).map(|address| Program::new(memory.to_vec(), Nic {
+            boot: Some(address as Value),
+            queue: VecDeque::new(),
+            pending_y: None,
+            out_state: OutputState::Empty,
+            outgoing: Vec::new(),
+            idle: false,
+            polled: false,
+        })).collect();
+
+        Network {
+            machines,
+            nat,
+            tick: 0,
+            log: None,
+            depth_log: Vec::new(),
+            stopped: false,
+        }
+    }
+    // Record every packet sent on the network, and the queue depths after every tick.
+    pub fn with_log(mut self) -> Self {
+        self.log = Some(Vec::new());
+        self
+    }
+    pub fn nat(&self) -> &N {
+        &self.nat
+    }
+    pub fn tick(&self) -> usize {
+        self.tick
+    }
+    pub fn packets(&self) -> &[Packet] {
+        self.log.as_deref().unwrap_or(&[])
+    }
+    pub fn capture(&self) -> Capture {
+        Capture {
+            count: self.machines.len(),
+            packets: self.packets().to_vec(),
+            depths: self.depth_log.clone(),
+        }
+    }
+    // Queue lengths of each machine, in address order.
+    pub fn queue_depths(&self) -> Vec<usize> {
+        self.machines.iter().map(|m| m.io.queue.len()).collect()
+    }
+    // The network is idle when no packets are queued and every machine has
+    // read -1 since it last received or sent anything.
+    pub fn is_idle(&self) -> bool {
+        self.machines.iter().all(|m| m.io.idle && m.io.queue.is_empty())
+    }
+    fn send(&mut self, src: usize, dst: usize, x: Value, y: Value) {
+        if let Some(log) = &mut self.log {
+            log.push(Packet { src, dst, x, y, tick: self.tick });
+        }
+        if dst == NAT_ADDRESS {
+            self.nat.receive(x, y);
+        } else if let Some(machine) = self.machines.get_mut(dst) {
+            machine.io.queue.push_back((x, y));
+        }
+        if self.nat.done() {
+            self.stopped = true;
+        }
+    }
+    // Gives each machine one turn, then wakes the NAT if the network is idle.
+    // Returns `false` once the simulation has stopped.
+    pub fn step(&mut self) -> bool {
+        if self.stopped {
+            return false;
+        }
+        for src in 0..self.machines.len() {
+            let machine = &mut self.machines[src];
+            machine.io.polled = false;
+            machine.run();
+            let outgoing = std::mem::take(&mut machine.io.outgoing);
+            for (dst, x, y) in outgoing {
+                self.send(src, dst, x, y);
+            }
+            if self.stopped {
+                return false;
+            }
+        }
+        if self.machines.iter().all(|m| m.halted()) {
+            self.stopped = true;
+        } else if self.is_idle() {
+            if let Some((x, y)) = self.nat.wake() {
+                self.send(NAT_ADDRESS, 0, x, y);
+            } else {
+                self.stopped = true;
+            }
+        }
+        if self.log.is_some() {
+            self.depth_log.push(self.queue_depths());
+        }
+        self.tick += 1;
+        !self.stopped
+    }
+    pub fn run(&mut self) {
+        while self.step() {}
+    }
+}
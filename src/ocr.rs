@@ -0,0 +1,148 @@
+use std::fmt;
+
+use crate::bitmap::Bitmap;
+
+// The 4x6 letters used by most puzzles. Some letters are narrower or wider
+// than four columns, which is fine since glyphs are split on blank columns.
+const SMALL: &[(char, [&str; 6])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+// The larger 6x10 letters.
+const LARGE: &[(char, [&str; 10])] = &[
+    ('A', ["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', ["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', [".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', [".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', ["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', ["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", "#...#.", ".###.."]),
+    ('K', ["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', ["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', ["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', ["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', ["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', ["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', ["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+];
+
+#[derive(Debug, Clone)]
+pub struct UnknownGlyph {
+    // Position of the glyph within the text.
+    pub index: usize,
+    pub bitmap: Bitmap,
+}
+
+#[derive(Debug, Clone)]
+pub struct OcrError {
+    // The text read, with `?` in place of each unknown glyph.
+    pub text: String,
+    pub unknown: Vec<UnknownGlyph>,
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Unrecognised glyphs in {:?}:", self.text)?;
+        for glyph in &self.unknown {
+            writeln!(f, "#{}:", glyph.index)?;
+            for row in glyph.bitmap.rows() {
+                let line: String = row.iter().map(|&p| if p { '#' } else { '.' }).collect();
+                writeln!(f, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+fn parse_glyph(rows: &[&str]) -> Bitmap {
+    let mut bitmap = Bitmap::new(rows[0].len(), rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            bitmap.set(x, y, c == '#');
+        }
+    }
+    bitmap
+}
+
+fn lookup(glyph: &Bitmap) -> Option<char> {
+    let small = SMALL.iter().map(|(c, rows)| (*c, parse_glyph(rows)));
+    let large = LARGE.iter().map(|(c, rows)| (*c, parse_glyph(rows)));
+    small.chain(large)
+        .find(|(_, font)| font == glyph)
+        .map(|(c, _)| c)
+}
+
+fn is_blank_column(bitmap: &Bitmap, x: usize) -> bool {
+    (0..bitmap.height).all(|y| !bitmap.get(x, y))
+}
+
+fn is_blank_row(bitmap: &Bitmap, y: usize) -> bool {
+    (0..bitmap.width).all(|x| !bitmap.get(x, y))
+}
+
+// Splits a bitmap into glyphs separated by blank columns, after trimming
+// any blank rows above and below the text.
+pub fn glyphs(bitmap: &Bitmap) -> Vec<Bitmap> {
+    let top = (0..bitmap.height).find(|&y| !is_blank_row(bitmap, y));
+    let bottom = (0..bitmap.height).rev().find(|&y| !is_blank_row(bitmap, y));
+    let (top, bottom) = match (top, bottom) {
+        (Some(top), Some(bottom)) => (top, bottom),
+        _ => return Vec::new(),
+    };
+    let height = bottom - top + 1;
+
+    let mut res = Vec::new();
+    let mut x = 0;
+    while x < bitmap.width {
+        if is_blank_column(bitmap, x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < bitmap.width && !is_blank_column(bitmap, x) {
+            x += 1;
+        }
+        res.push(bitmap.crop(start, top, x - start, height));
+    }
+    res
+}
+
+// Reads the letters drawn in a bitmap.
+pub fn read(bitmap: &Bitmap) -> Result<String, OcrError> {
+    let mut text = String::new();
+    let mut unknown = Vec::new();
+    for (index, glyph) in glyphs(bitmap).into_iter().enumerate() {
+        match lookup(&glyph) {
+            Some(c) => text.push(c),
+            None => {
+                text.push('?');
+                unknown.push(UnknownGlyph { index, bitmap: glyph });
+            },
+        }
+    }
+    if unknown.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError { text, unknown })
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use crate::bitmap::Bitmap;
+use crate::grid::{Direction, Point};
+use crate::intcode::{Io, Program, Value};
+use crate::render::{Frame, Glyph, Screen};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Colour {
+    Black = 0,
+    White = 1,
+}
+
+impl From<Value> for Colour {
+    fn from(other: Value) -> Self {
+        match other {
+            0 => Colour::Black,
+            1 => Colour::White,
+            _ => panic!("Unknown colour: {}", other),
+        }
+    }
+}
+
+// The robot itself, independent of the program controlling it.
+#[derive(Debug, Clone)]
+pub struct Robot {
+    pub pos: Point,
+    pub dir: Direction,
+    // Every panel painted at least once, with its current colour.
+    pub panels: HashMap<Point, Colour>,
+    start: Colour,
+    moving: bool,
+}
+
+impl Robot {
+    pub fn new(start: Colour) -> Self {
+        Robot {
+            pos: (0, 0),
+            dir: Direction::North,
+            panels: HashMap::new(),
+            start,
+            moving: false,
+        }
+    }
+    pub fn colour_at(&self, pos: Point) -> Colour {
+        match self.panels.get(&pos) {
+            Some(&colour) => colour,
+            None if pos == (0, 0) => self.start,
+            None => Colour::Black,
+        }
+    }
+    pub fn camera(&self) -> Colour {
+        self.colour_at(self.pos)
+    }
+    pub fn paint(&mut self, colour: Colour) {
+        self.panels.insert(self.pos, colour);
+    }
+    // Turns left for 0 or right for 1, then moves forward one panel.
+    pub fn turn_and_move(&mut self, turn: Value) {
+        self.dir = if turn == 0 {
+            self.dir.left()
+        } else {
+            self.dir.right()
+        };
+        self.pos = self.dir.apply(self.pos);
+    }
+    pub fn painted_count(&self) -> usize {
+        self.panels.len()
+    }
+    // The white panels of the hull, cropped to fit.
+    pub fn hull(&self) -> Bitmap {
+        let mut white: Vec<_> = self.panels.iter()
+            .filter(|&(_, &c)| c == Colour::White)
+            .map(|(&pos, _)| pos)
+            .collect();
+        if self.start == Colour::White && !self.panels.contains_key(&(0, 0)) {
+            white.push((0, 0));
+        }
+        Bitmap::from_points(white)
+    }
+}
+
+// Called after every instruction the robot carries out.
+pub trait Renderer {
+    fn render(&mut self, robot: &Robot);
+}
+
+impl Renderer for () {
+    fn render(&mut self, _robot: &Robot) {}
+}
+
+impl Glyph for Colour {
+    fn glyph(&self) -> char {
+        (*self == Colour::White).glyph()
+    }
+}
+
+// Draws the hull and robot as it goes.
+impl Renderer for Screen {
+    fn render(&mut self, robot: &Robot) {
+        let mut frame = Frame::from_tiles(robot.panels.iter().map(|(&pos, colour)| (pos, *colour)));
+        let c = match robot.dir {
+            Direction::West => '\u{2C2}',
+            Direction::North => '\u{2C4}',
+            Direction::East => '\u{2C3}',
+            Direction::South => '\u{2C5}',
+        };
+        frame.set(robot.pos, &c);
+        self.draw(frame);
+    }
+}
+
+struct IoState<R> {
+    robot: Robot,
+    renderer: R,
+}
+
+impl<R: Renderer> Io for IoState<R> {
+    fn input(&mut self) -> Option<Value> {
+        Some(self.robot.camera() as Value)
+    }
+    fn output(&mut self, value: Value) {
+        if self.robot.moving {
+            self.robot.turn_and_move(value);
+        } else {
+            self.robot.paint(value.into());
+        }
+        self.robot.moving = !self.robot.moving;
+        self.renderer.render(&self.robot);
+    }
+}
+
+// Runs the painting program to completion, starting on a panel of the given colour.
+pub fn paint(memory: Vec<Value>, start: Colour, renderer: impl Renderer) -> Robot {
+    let mut program = Program::new(memory, IoState {
+        robot: Robot::new(start),
+        renderer,
+    });
+    program.run();
+    program.io.robot
+}
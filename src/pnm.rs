@@ -0,0 +1,29 @@
+// Plain (ASCII) Netpbm encoders, which almost any image viewer can open.
+
+pub type Rgb = [u8; 3];
+
+// A black and white image, `true` being black.
+pub fn pbm(width: usize, height: usize, pixel: impl Fn(usize, usize) -> bool) -> String {
+    let mut s = format!("P1\n{} {}\n", width, height);
+    for y in 0..height {
+        let row: Vec<_> = (0..width).map(|x| if pixel(x, y) { "1" } else { "0" }).collect();
+        s += &row.join(" ");
+        s.push('\n');
+    }
+    s
+}
+
+pub fn ppm(width: usize, height: usize, pixel: impl Fn(usize, usize) -> Rgb) -> String {
+    let mut s = format!("P3\n{} {}\n255\n", width, height);
+    for y in 0..height {
+        let row: Vec<_> = (0..width)
+            .map(|x| {
+                let [r, g, b] = pixel(x, y);
+                format!("{} {} {}", r, g, b)
+            })
+            .collect();
+        s += &row.join(" ");
+        s.push('\n');
+    }
+    s
+}
@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use crate::grid::{self, Point};
+use crate::search;
+
+pub const START: &str = "AA";
+pub const END: &str = "ZZ";
+
+// Which edge of the donut a portal is on. Stepping through an inner portal
+// leads one level deeper into a recursive maze, and an outer one back out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Side {
+    Outer,
+    Inner,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Portal {
+    pub label: String,
+    // The open tile in front of the label.
+    pub pos: Point,
+    pub side: Side,
+}
+
+// A single step through the maze.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Move {
+    Walk(Point),
+    // Through the portal with the given index, arriving at `to`.
+    Warp { portal: usize, to: Point },
+}
+
+impl Move {
+    pub fn to(self) -> Point {
+        match self {
+            Move::Walk(to) | Move::Warp { to, .. } => to,
+        }
+    }
+}
+
+// A parsed donut maze: its open tiles, plus the portals joining pairs of them.
+#[derive(Debug, Clone)]
+pub struct PortalMaze {
+    open: HashSet<Point>,
+    pub portals: Vec<Portal>,
+    // The portal in front of each tile which has one.
+    portal_at: HashMap<Point, usize>,
+    // The other end of each portal, except the entrance and exit.
+    partners: Vec<Option<usize>>,
+    pub start: Point,
+    pub end: Point,
+}
+
+impl PortalMaze {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut open = HashSet::new();
+        let mut walls = HashSet::new();
+        let mut letters = HashMap::new();
+        for (y, line) in input.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let pos = (x as i64, y as i64);
+                match c {
+                    '.' => { open.insert(pos); },
+                    '#' => { walls.insert(pos); },
+                    ' ' => {},
+                    c if c.is_ascii_uppercase() => { letters.insert(pos, c); },
+                    _ => return Err(format!("Unexpected character {:?} at {:?}", c, pos)),
+                }
+            }
+        }
+
+        // The outer edge of the donut is the bounding box of the maze itself,
+        // ignoring the labels around it.
+        let (min_x, min_y, max_x, max_y) = grid::bounds(open.iter().chain(&walls).copied());
+
+        let mut portals = Vec::new();
+        let mut portal_at = HashMap::new();
+        let mut used = HashSet::new();
+        let mut positions: Vec<_> = letters.keys().copied().collect();
+        positions.sort_by_key(|&(x, y)| (y, x));
+        for (x, y) in positions {
+            if used.contains(&(x, y)) {
+                continue;
+            }
+            // Labels read left to right or top to bottom, so this is always
+            // the first letter of its label.
+            let second = [(x + 1, y), (x, y + 1)].iter().copied().find(|pos| letters.contains_key(pos));
+            let second = second.ok_or_else(|| format!("Stray letter {:?} at {:?}", letters[&(x, y)], (x, y)))?;
+            let (dx, dy) = (second.0 - x, second.1 - y);
+            let before = (x - dx, y - dy);
+            let after = (second.0 + dx, second.1 + dy);
+            let pos = match (open.contains(&before), open.contains(&after)) {
+                (true, false) => before,
+                (false, true) => after,
+                _ => return Err(format!("Label at {:?} is not next to exactly one open tile", (x, y))),
+            };
+            let label: String = [letters[&(x, y)], letters[&second]].iter().collect();
+            let side = if pos.0 == min_x || pos.0 == max_x || pos.1 == min_y || pos.1 == max_y {
+                Side::Outer
+            } else {
+                Side::Inner
+            };
+            used.insert((x, y));
+            used.insert(second);
+            if portal_at.insert(pos, portals.len()).is_some() {
+                return Err(format!("More than one label at {:?}", pos));
+            }
+            portals.push(Portal { label, pos, side });
+        }
+
+        let mut by_label: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (i, portal) in portals.iter().enumerate() {
+            by_label.entry(&portal.label).or_default().push(i);
+        }
+        let mut partners = vec![None; portals.len()];
+        for (&label, ends) in &by_label {
+            if label == START || label == END {
+                if ends.len() != 1 {
+                    return Err(format!("{} appears {} times", label, ends.len()));
+                }
+                continue;
+            }
+            match ends[..] {
+                [a, b] if portals[a].side != portals[b].side => {
+                    partners[a] = Some(b);
+                    partners[b] = Some(a);
+                },
+                [_, _] => return Err(format!("Both ends of {} are on the same side", label)),
+                _ => return Err(format!("{} appears {} times, not twice", label, ends.len())),
+            }
+        }
+
+        let find = |label: &str| {
+            by_label.get(label)
+                .map(|ends| portals[ends[0]].pos)
+                .ok_or_else(|| format!("Maze has no {}", label))
+        };
+        let start = find(START)?;
+        let end = find(END)?;
+
+        Ok(PortalMaze { open, portals, portal_at, partners, start, end })
+    }
+    pub fn is_open(&self, pos: Point) -> bool {
+        self.open.contains(&pos)
+    }
+    pub fn portal_at(&self, pos: Point) -> Option<usize> {
+        self.portal_at.get(&pos).copied()
+    }
+    pub fn partner(&self, portal: usize) -> Option<usize> {
+        self.partners[portal]
+    }
+    // Every step which can be taken from `pos`.
+    pub fn moves(&self, pos: Point) -> impl Iterator<Item=Move> + '_ {
+        let walks = grid::neighbours(pos)
+            .map(|(_, new_pos)| new_pos)
+            .filter(move |&new_pos| self.is_open(new_pos))
+            .map(Move::Walk);
+        let warp = self.portal_at(pos).and_then(|portal| {
+            self.partner(portal).map(|other| Move::Warp { portal, to: self.portals[other].pos })
+        });
+        walks.chain(warp)
+    }
+}
+
+// Which way a warp goes in a recursive maze.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Warp {
+    // Through an inner portal, one level deeper.
+    Inward,
+    // Through an outer portal, back up a level.
+    Outward,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub label: String,
+    pub direction: Warp,
+    // The level arrived at.
+    pub level: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub steps: usize,
+    // Every portal passed through between AA and ZZ.
+    pub warps: Vec<Step>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NoPath {
+    // The deepest level searched, for recursive mazes.
+    pub max_depth: Option<usize>,
+}
+
+impl fmt::Display for NoPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.max_depth {
+            Some(depth) => write!(f, "No path from {} to {} within depth {}", START, END, depth),
+            None => write!(f, "No path from {} to {}", START, END),
+        }
+    }
+}
+
+impl std::error::Error for NoPath {}
+
+impl PortalMaze {
+    // The walking distance from each portal to every other portal reachable
+    // from it without warping.
+    fn walks(&self) -> Vec<Vec<(usize, usize)>> {
+        self.portals.iter().enumerate().map(|(i, portal)| {
+            let dists = search::distances(vec![portal.pos], |&pos| {
+                grid::neighbours(pos)
+                    .map(|(_, new_pos)| new_pos)
+                    .filter(|&new_pos| self.is_open(new_pos))
+                    .collect::<Vec<_>>()
+            });
+            let mut res: Vec<_> = dists.into_iter()
+                .filter_map(|(pos, dist)| self.portal_at(pos).map(|j| (j, dist)))
+                .filter(|&(j, _)| j != i)
+                .collect();
+            res.sort();
+            res
+        }).collect()
+    }
+    // Searches the graph of portals, where each warp costs one step. With a
+    // maximum depth the maze is recursive, and ZZ is only open at level 0.
+    // Nodes record whether they were reached by warping, since a portal's
+    // partner may also be reachable on foot.
+    fn solve(&self, max_depth: Option<usize>) -> Result<Route, NoPath> {
+        let walks = self.walks();
+        let start = self.portal_at(self.start).unwrap();
+        let end = self.portal_at(self.end).unwrap();
+
+        let path = search::dijkstra(
+            vec![(start, 0usize, false)],
+            |&(i, level, _)| {
+                let mut res: Vec<_> = walks[i].iter()
+                    .map(|&(j, dist)| ((j, level, false), dist))
+                    .collect();
+                if let Some(j) = self.partner(i) {
+                    let new_level = match (max_depth, self.portals[i].side) {
+                        (None, _) => Some(level),
+                        (Some(max), Side::Inner) => Some(level + 1).filter(|&l| l <= max),
+                        (Some(_), Side::Outer) => level.checked_sub(1),
+                    };
+                    if let Some(new_level) = new_level {
+                        res.push(((j, new_level, true), 1));
+                    }
+                }
+                res
+            },
+            |&(i, level, _)| (i, level) == (end, 0),
+        ).ok_or(NoPath { max_depth })?;
+
+        let warps = path.nodes.windows(2)
+            .filter(|w| w[1].2)
+            .map(|w| {
+                let portal = &self.portals[w[0].0];
+                Step {
+                    label: portal.label.clone(),
+                    direction: match portal.side {
+                        Side::Inner => Warp::Inward,
+                        Side::Outer => Warp::Outward,
+                    },
+                    level: w[1].1,
+                }
+            })
+            .collect();
+        Ok(Route { steps: path.cost, warps })
+    }
+    // Portals lead straight to their partners.
+    pub fn solve_flat(&self) -> Result<Route, NoPath> {
+        self.solve(None)
+    }
+    // Each portal leads to a copy of the maze one level in or out, going no
+    // deeper than `max_depth`.
+    pub fn solve_recursive(&self, max_depth: usize) -> Result<Route, NoPath> {
+        self.solve(Some(max_depth))
+    }
+}
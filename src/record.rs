@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::pnm::{self, Rgb};
+use crate::render::{AnsiEncoder, Backend, Frame, Pos, Screen, Tee, Terminal};
+
+// Recorders timestamp frames with a virtual clock ticking at a fixed frame
+// rate rather than the wall clock, so a recording plays back the same however
+// fast the run which produced it was.
+fn timestamp(index: usize, fps: u32) -> f64 {
+    index as f64 / f64::from(fps.max(1))
+}
+
+fn json_string(s: &str) -> String {
+    let mut res = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => res += "\\\"",
+            '\\' => res += "\\\\",
+            '\n' => res += "\\n",
+            '\r' => res += "\\r",
+            '\t' => res += "\\t",
+            c if (c as u32) < 0x20 => res += &format!("\\u{:04x}", c as u32),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+// Records frames as an asciinema v2 `.cast` file, written once the last
+// frame has been drawn.
+#[derive(Debug)]
+pub struct Cast {
+    path: PathBuf,
+    fps: u32,
+    encoder: AnsiEncoder,
+    events: Vec<(f64, String)>,
+    width: usize,
+    height: usize,
+}
+
+impl Cast {
+    pub fn new(path: impl Into<PathBuf>, fps: u32) -> Self {
+        Cast {
+            path: path.into(),
+            fps,
+            encoder: AnsiEncoder::absolute(),
+            events: Vec::new(),
+            width: 0,
+            height: 0,
+        }
+    }
+    fn push(&mut self, frame: &Frame, data: String) {
+        let lines = frame.lines();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        self.width = self.width.max(width).max(frame.status.chars().count());
+        // The frame, a blank line, the status and the cursor line.
+        self.height = self.height.max(lines.len() + 3);
+        self.events.push((timestamp(self.events.len(), self.fps), data));
+    }
+    pub fn to_cast(&self) -> String {
+        let mut s = format!(
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"env\": {{\"TERM\": \"xterm-256color\"}}}}\n",
+            self.width.max(1),
+            self.height.max(1),
+        );
+        for (time, data) in &self.events {
+            s += &format!("[{:.6}, \"o\", {}]\n", time, json_string(data));
+        }
+        s
+    }
+    pub fn save(&self) -> io::Result<()> {
+        fs::write(&self.path, self.to_cast())
+    }
+}
+
+impl Backend for Cast {
+    fn redraw(&mut self, frame: &Frame) {
+        let data = self.encoder.redraw(frame);
+        self.push(frame, data);
+    }
+    fn update(&mut self, frame: &Frame, changed: &[Pos], status_changed: bool) {
+        let data = self.encoder.update(frame, changed, status_changed);
+        self.push(frame, data);
+    }
+    fn finish(&mut self) {
+        if let Err(e) = self.save() {
+            eprintln!("Failed to save {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+// Writes every frame as a numbered PPM image, `<prefix>00000.ppm` onwards,
+// plus `<prefix>index.txt` listing each image with its timestamp. Each cell
+// becomes a square block of pixels coloured according to a palette.
+#[derive(Debug)]
+pub struct PpmSequence {
+    prefix: String,
+    fps: u32,
+    scale: usize,
+    palette: HashMap<char, Rgb>,
+    // Anything not in the palette.
+    foreground: Rgb,
+    index: Vec<(f64, String)>,
+    error: Option<io::Error>,
+}
+
+impl PpmSequence {
+    pub fn new(prefix: impl Into<String>, fps: u32) -> Self {
+        let mut palette = HashMap::new();
+        palette.insert(' ', [0, 0, 0]);
+        palette.insert('.', [96, 96, 96]);
+        PpmSequence {
+            prefix: prefix.into(),
+            fps,
+            scale: 4,
+            palette,
+            foreground: [255, 255, 255],
+            index: Vec::new(),
+            error: None,
+        }
+    }
+    pub fn with_scale(mut self, scale: usize) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+    pub fn with_colour(mut self, c: char, colour: Rgb) -> Self {
+        self.palette.insert(c, colour);
+        self
+    }
+    fn colour(&self, c: char) -> Rgb {
+        self.palette.get(&c).copied().unwrap_or(self.foreground)
+    }
+    pub fn to_ppm(&self, frame: &Frame) -> String {
+        let (min_x, min_y, max_x, max_y) = frame.bounds();
+        let width = (max_x - min_x + 1) as usize;
+        let height = (max_y - min_y + 1) as usize;
+        let scale = self.scale;
+        pnm::ppm(width*scale, height*scale, |x, y| {
+            self.colour(frame.get((min_x + (x/scale) as i64, min_y + (y/scale) as i64)))
+        })
+    }
+    fn write(&mut self, frame: &Frame) {
+        if self.error.is_some() {
+            return;
+        }
+        let name = format!("{}{:05}.ppm", self.prefix, self.index.len());
+        match fs::write(&name, self.to_ppm(frame)) {
+            Ok(()) => self.index.push((timestamp(self.index.len(), self.fps), name)),
+            Err(e) => {
+                eprintln!("Failed to save {}: {}", name, e);
+                self.error = Some(e);
+            },
+        }
+    }
+}
+
+impl Backend for PpmSequence {
+    fn redraw(&mut self, frame: &Frame) {
+        self.write(frame);
+    }
+    fn update(&mut self, frame: &Frame, _changed: &[Pos], _status_changed: bool) {
+        self.write(frame);
+    }
+    fn finish(&mut self) {
+        let index: String = self.index.iter()
+            .map(|(time, name)| format!("{:.6} {}\n", time, name))
+            .collect();
+        let name = format!("{}index.txt", self.prefix);
+        if let Err(e) = fs::write(&name, index) {
+            eprintln!("Failed to save {}: {}", name, e);
+        }
+    }
+}
+
+// A `.cast` recorder for paths ending in `.cast`, otherwise a PPM sequence
+// using the path as the file name prefix.
+pub fn recorder(path: impl AsRef<Path>, fps: u32) -> Box<dyn Backend> {
+    let path = path.as_ref();
+    if path.extension().is_some_and(|e| e == "cast") {
+        Box::new(Cast::new(path, fps))
+    } else {
+        Box::new(PpmSequence::new(path.to_string_lossy(), fps))
+    }
+}
+
+// Like `Screen::choose`, additionally recording every frame to `record` if
+// given. Frames are only rate limited when shown in the terminal.
+pub fn screen(show: bool, fps: u32, record: Option<&str>) -> Screen {
+    match (show, record) {
+        (true, Some(path)) => Screen::new(Tee(Terminal::new(), recorder(path, fps)), Some(fps)),
+        (false, Some(path)) => Screen::new(recorder(path, fps), None),
+        (show, None) => Screen::choose(show, fps),
+    }
+}
+
+// Removes `--record <path>` from command line arguments, returning the path.
+pub fn take_arg(args: &mut Vec<String>) -> Option<String> {
+    let i = args.iter().position(|arg| arg == "--record")?;
+    args.remove(i);
+    if i < args.len() {
+        Some(args.remove(i))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::grid;
+
+pub use crate::grid::Point as Pos;
+
+// How a tile is drawn.
+pub trait Glyph {
+    fn glyph(&self) -> char;
+}
+
+impl Glyph for char {
+    fn glyph(&self) -> char {
+        *self
+    }
+}
+
+impl Glyph for bool {
+    fn glyph(&self) -> char {
+        if *self { '\u{2588}' } else { ' ' }
+    }
+}
+
+// A complete picture to be drawn, plus a line of status text underneath.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frame {
+    cells: HashMap<Pos, char>,
+    pub status: String,
+}
+
+impl Frame {
+    pub fn new() -> Self {
+        Frame::default()
+    }
+    pub fn from_tiles<T: Glyph>(tiles: impl IntoIterator<Item=(Pos, T)>) -> Self {
+        let mut frame = Frame::new();
+        for (pos, tile) in tiles {
+            frame.set(pos, &tile);
+        }
+        frame
+    }
+    pub fn set(&mut self, pos: Pos, tile: &impl Glyph) {
+        self.cells.insert(pos, tile.glyph());
+    }
+    pub fn get(&self, pos: Pos) -> char {
+        self.cells.get(&pos).copied().unwrap_or(' ')
+    }
+    // Returns (min_x, min_y, max_x, max_y).
+    pub fn bounds(&self) -> (i64, i64, i64, i64) {
+        grid::bounds(self.cells.keys().copied())
+    }
+    pub fn lines(&self) -> Vec<String> {
+        let (min_x, min_y, max_x, max_y) = self.bounds();
+        (min_y..=max_y)
+            .map(|y| (min_x..=max_x).map(|x| self.get((x, y))).collect())
+            .collect()
+    }
+}
+
+// Where frames end up.
+pub trait Backend {
+    // Draws a frame from scratch.
+    fn redraw(&mut self, frame: &Frame);
+    // Draws only the given cells of a frame, which has the same bounds as
+    // the previous one.
+    fn update(&mut self, frame: &Frame, changed: &[Pos], status_changed: bool);
+    // Called once the last frame has been drawn.
+    fn finish(&mut self) {}
+}
+
+impl<B: Backend + ?Sized> Backend for Box<B> {
+    fn redraw(&mut self, frame: &Frame) {
+        (**self).redraw(frame)
+    }
+    fn update(&mut self, frame: &Frame, changed: &[Pos], status_changed: bool) {
+        (**self).update(frame, changed, status_changed)
+    }
+    fn finish(&mut self) {
+        (**self).finish()
+    }
+}
+
+// Sends every frame to two backends, eg. to record while watching.
+#[derive(Debug)]
+pub struct Tee<A, B>(pub A, pub B);
+
+impl<A: Backend, B: Backend> Backend for Tee<A, B> {
+    fn redraw(&mut self, frame: &Frame) {
+        self.0.redraw(frame);
+        self.1.redraw(frame);
+    }
+    fn update(&mut self, frame: &Frame, changed: &[Pos], status_changed: bool) {
+        self.0.update(frame, changed, status_changed);
+        self.1.update(frame, changed, status_changed);
+    }
+    fn finish(&mut self) {
+        self.0.finish();
+        self.1.finish();
+    }
+}
+
+// Discards everything, for running without a terminal.
+#[derive(Debug, Default)]
+pub struct Headless;
+
+impl Backend for Headless {
+    fn redraw(&mut self, _frame: &Frame) {}
+    fn update(&mut self, _frame: &Frame, _changed: &[Pos], _status_changed: bool) {}
+}
+
+// Turns frames into ANSI escape sequences. Frames are either drawn relative
+// to the cursor position when the first frame was drawn, or from the top-left
+// corner of the screen.
+#[derive(Debug, Default)]
+pub struct AnsiEncoder {
+    absolute: bool,
+    started: bool,
+    height: i64,
+}
+
+impl AnsiEncoder {
+    pub fn relative() -> Self {
+        AnsiEncoder::default()
+    }
+    pub fn absolute() -> Self {
+        AnsiEncoder {
+            absolute: true,
+            ..AnsiEncoder::default()
+        }
+    }
+    fn move_to(&self, s: &mut String, row: i64, col: i64) {
+        if self.absolute {
+            *s += &format!("\x1B[{};{}H", row + 1, col + 1);
+            return;
+        }
+        *s += "\x1B[u";
+        if row > 0 {
+            *s += &format!("\x1B[{}B", row);
+        }
+        if col > 0 {
+            *s += &format!("\x1B[{}C", col);
+        }
+    }
+    pub fn redraw(&mut self, frame: &Frame) -> String {
+        let mut s = String::new();
+        if self.absolute {
+            s += "\x1B[H\x1B[J";
+        } else {
+            if !self.started {
+                s += "\n\x1B[s";
+                self.started = true;
+            }
+            s += "\x1B[u\x1B[J";
+        }
+        let lines = frame.lines();
+        for line in &lines {
+            s += line;
+            s += "\r\n";
+        }
+        self.height = lines.len() as i64;
+        s += &format!("\r\n{}\r\n", frame.status);
+        s
+    }
+    pub fn update(&mut self, frame: &Frame, changed: &[Pos], status_changed: bool) -> String {
+        let (min_x, min_y, _, _) = frame.bounds();
+        let mut s = String::new();
+        for &(x, y) in changed {
+            self.move_to(&mut s, y - min_y, x - min_x);
+            s.push(frame.get((x, y)));
+        }
+        if status_changed {
+            self.move_to(&mut s, self.height + 1, 0);
+            s += "\x1B[K";
+            s += &frame.status;
+        }
+        // Leave the cursor below the frame.
+        self.move_to(&mut s, self.height + 2, 0);
+        s
+    }
+}
+
+// Draws frames in place in the terminal.
+#[derive(Debug, Default)]
+pub struct Terminal {
+    encoder: AnsiEncoder,
+}
+
+impl Terminal {
+    pub fn new() -> Self {
+        Terminal::default()
+    }
+    fn write(s: &str) {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(s.as_bytes()).unwrap();
+        stdout.flush().unwrap();
+    }
+}
+
+impl Backend for Terminal {
+    fn redraw(&mut self, frame: &Frame) {
+        Terminal::write(&self.encoder.redraw(frame));
+    }
+    fn update(&mut self, frame: &Frame, changed: &[Pos], status_changed: bool) {
+        Terminal::write(&self.encoder.update(frame, changed, status_changed));
+    }
+}
+
+// Keeps frames from being drawn faster than a given rate, only sleeping for
+// whatever part of the frame interval was not spent doing real work.
+#[derive(Debug)]
+pub struct FrameLimiter {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl FrameLimiter {
+    pub fn new(fps: u32) -> Self {
+        FrameLimiter {
+            interval: Duration::from_secs(1) / fps.max(1),
+            last: None,
+        }
+    }
+    pub fn wait(&mut self) {
+        if let Some(last) = self.last {
+            let elapsed = last.elapsed();
+            if elapsed < self.interval {
+                thread::sleep(self.interval - elapsed);
+            }
+        }
+        self.last = Some(Instant::now());
+    }
+}
+
+// Draws a sequence of frames, sending only the cells which changed since the
+// previous frame to the backend.
+pub struct Screen {
+    backend: Box<dyn Backend>,
+    limiter: Option<FrameLimiter>,
+    prev: Option<Frame>,
+}
+
+impl Screen {
+    pub fn new(backend: impl Backend + 'static, fps: Option<u32>) -> Self {
+        Screen {
+            backend: Box::new(backend),
+            limiter: fps.map(FrameLimiter::new),
+            prev: None,
+        }
+    }
+    pub fn terminal(fps: u32) -> Self {
+        Screen::new(Terminal::new(), Some(fps))
+    }
+    pub fn headless() -> Self {
+        Screen::new(Headless, None)
+    }
+    // A terminal screen if `show` is set, otherwise a headless one.
+    pub fn choose(show: bool, fps: u32) -> Self {
+        if show {
+            Screen::terminal(fps)
+        } else {
+            Screen::headless()
+        }
+    }
+    pub fn draw(&mut self, frame: Frame) {
+        if let Some(limiter) = &mut self.limiter {
+            limiter.wait();
+        }
+        match &self.prev {
+            Some(prev) if prev.bounds() == frame.bounds() => {
+                let (min_x, min_y, max_x, max_y) = frame.bounds();
+                let changed: Vec<Pos> = (min_y..=max_y)
+                    .flat_map(|y| (min_x..=max_x).map(move |x| (x, y)))
+                    .filter(|&pos| prev.get(pos) != frame.get(pos))
+                    .collect();
+                let status_changed = prev.status != frame.status;
+                if !changed.is_empty() || status_changed {
+                    self.backend.update(&frame, &changed, status_changed);
+                }
+            },
+            _ => self.backend.redraw(&frame),
+        }
+        self.prev = Some(frame);
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        self.backend.finish();
+    }
+}
+
+impl std::fmt::Debug for Screen {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Screen")
+            .field("limiter", &self.limiter)
+            .field("prev", &self.prev)
+            .finish()
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::grid::{self, SparseGrid};
+use crate::intcode::{Io, Program, Value};
+use crate::render::{Frame, Glyph, Screen};
+use crate::search;
+
+pub use crate::grid::{Direction, Point as Pos};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Cell {
+    Empty,
+    Wall,
+    OxygenSystem,
+}
+
+// The part of the ship discovered by the droid. Positions are relative to
+// where the droid started, which is always at the origin.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShipMap {
+    cells: SparseGrid<Cell>,
+}
+
+impl ShipMap {
+    pub fn get(&self, pos: Pos) -> Option<Cell> {
+        self.cells.get(pos).copied()
+    }
+    pub fn is_open(&self, pos: Pos) -> bool {
+        matches!(self.get(pos), Some(Cell::Empty) | Some(Cell::OxygenSystem))
+    }
+    pub fn oxygen_pos(&self) -> Option<Pos> {
+        self.cells.find(|&cell| cell == Cell::OxygenSystem)
+    }
+    // Returns (min_x, min_y, max_x, max_y) of the discovered cells.
+    pub fn bounds(&self) -> (i64, i64, i64, i64) {
+        self.cells.bounds()
+    }
+    pub fn neighbours(&self, pos: Pos) -> impl Iterator<Item=(Direction, Pos)> + '_ {
+        grid::neighbours(pos)
+            .filter(move |&(_, new_pos)| self.is_open(new_pos))
+    }
+    // Breadth-first distance to every open cell reachable from `from`.
+    pub fn distances_from(&self, from: Pos) -> HashMap<Pos, usize> {
+        search::distances(vec![from], |&pos| self.neighbours(pos).map(|(_, new_pos)| new_pos).collect::<Vec<_>>())
+    }
+    // The moves needed to get from `from` to `to` by the shortest route.
+    pub fn path(&self, from: Pos, to: Pos) -> Option<Vec<Direction>> {
+        let path = search::bfs(
+            vec![from],
+            |&pos| self.neighbours(pos).map(|(_, new_pos)| new_pos).collect::<Vec<_>>(),
+            |&pos| pos == to,
+        )?;
+        Some(path.nodes.windows(2)
+            .map(|w| Direction::from_offset((w[1].0 - w[0].0, w[1].1 - w[0].1)).unwrap())
+            .collect())
+    }
+    pub fn path_to_oxygen(&self) -> Option<Vec<Direction>> {
+        self.path((0, 0), self.oxygen_pos()?)
+    }
+    // Minutes until oxygen has spread from the oxygen system to every open cell.
+    pub fn oxygen_fill_time(&self) -> Option<usize> {
+        self.distances_from(self.oxygen_pos()?).values().max().copied()
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+// Maps are stored as ASCII art: `#` for walls, `.` for open cells, `O` for the
+// oxygen system, `S` for the droid's starting cell and spaces for unknown cells.
+impl fmt::Display for ShipMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.cells.render(|pos, cell| match cell {
+            _ if pos == (0, 0) => 'S',
+            Some(Cell::Empty) => '.',
+            Some(Cell::Wall) => '#',
+            Some(Cell::OxygenSystem) => 'O',
+            None => ' ',
+        }))
+    }
+}
+
+impl FromStr for ShipMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut start = None;
+        let cells = SparseGrid::parse(s, |pos, c| Ok(Some(match c {
+            '.' => Cell::Empty,
+            '#' => Cell::Wall,
+            'O' => Cell::OxygenSystem,
+            'S' => {
+                start = Some(pos);
+                Cell::Empty
+            },
+            ' ' => return Ok(None),
+            other => return Err(format!("Unknown map character: {:?}", other)),
+        })))?;
+        let (sx, sy) = start.ok_or("Map has no starting cell")?;
+        Ok(ShipMap {
+            cells: cells.iter().map(|((x, y), &cell)| ((x - sx, y - sy), cell)).collect(),
+        })
+    }
+}
+
+impl Glyph for Cell {
+    fn glyph(&self) -> char {
+        match self {
+            Cell::Empty => ' ',
+            Cell::Wall => '\u{2588}',
+            Cell::OxygenSystem => 'o',
+        }
+    }
+}
+
+#[derive(Debug)]
+struct IoState {
+    map: ShipMap,
+    pos: Pos,
+    stack: Vec<Direction>,
+    attempt: Direction,
+    screen: Screen,
+}
+
+impl IoState {
+    fn display(&mut self) {
+        let (min_x, min_y, max_x, max_y) = self.map.bounds();
+        let mut frame = Frame::new();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                match self.map.get((x, y)) {
+                    Some(cell) => frame.set((x, y), &cell),
+                    None => frame.set((x, y), &'?'),
+                }
+            }
+        }
+        frame.set(self.pos, &'@');
+        self.screen.draw(frame);
+    }
+}
+
+impl Io for IoState {
+    fn input(&mut self) -> Option<Value> {
+        self.display();
+        for &dir in &Direction::ALL {
+            let new_pos = dir.apply(self.pos);
+            if !self.map.cells.contains(new_pos) {
+                self.attempt = dir;
+                return Some(dir.code());
+            }
+        }
+        if let Some(back_dir) = self.stack.pop() {
+            self.attempt = back_dir;
+            Some(back_dir.code())
+        } else {
+            None
+        }
+    }
+    fn output(&mut self, value: Value) {
+        let new_pos = self.attempt.apply(self.pos);
+        let (moved, cell) = match value {
+            0 => (false, Cell::Wall),
+            1 => (true, Cell::Empty),
+            2 => (true, Cell::OxygenSystem),
+            _ => panic!("Unknown output: {}", value),
+        };
+
+        let visited = self.map.cells.insert(new_pos, cell).is_some();
+        if moved {
+            if !visited {
+                self.stack.push(self.attempt.rev());
+            }
+            self.pos = new_pos;
+        }
+    }
+}
+
+// Drives the droid program depth-first until every reachable cell is known.
+pub fn explore(memory: Vec<Value>, screen: Screen) -> ShipMap {
+    let mut map = ShipMap::default();
+    map.cells.insert((0, 0), Cell::Empty);
+
+    let mut program = Program::new(memory, IoState {
+        map,
+        pos: (0, 0),
+        stack: Vec::new(),
+        attempt: Direction::North,
+        screen,
+    });
+    program.run();
+
+    program.io.display();
+    program.io.map
+}
+
+// Loads a previously explored map from `path` if there is one, otherwise
+// explores the ship and saves the result there for next time.
+pub fn explore_cached(memory: Vec<Value>, path: impl AsRef<Path>, screen: Screen) -> io::Result<ShipMap> {
+    let path = path.as_ref();
+    if path.exists() {
+        ShipMap::load(path)
+    } else {
+        let map = explore(memory, screen);
+        map.save(path)?;
+        Ok(map)
+    }
+}
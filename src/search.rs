@@ -0,0 +1,347 @@
+// Shortest path searches over implicit graphs, where the edges leaving a node
+// are produced on demand by a successor function.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::collections::hash_map::Entry;
+use std::hash::Hash;
+use std::ops::Add;
+
+// The cheapest route found by a search, from one of the start nodes to a goal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N, C> {
+    pub cost: C,
+    // Every node visited, including the start and the goal.
+    pub nodes: Vec<N>,
+}
+
+impl<N, C> Path<N, C> {
+    pub fn start(&self) -> &N {
+        &self.nodes[0]
+    }
+    pub fn goal(&self) -> &N {
+        &self.nodes[self.nodes.len() - 1]
+    }
+}
+
+// Nodes seen so far, numbered in the order they were found so the queues
+// only need to hold indices.
+struct Seen<N, C> {
+    index: HashMap<N, usize>,
+    nodes: Vec<(N, C, Option<usize>)>,
+}
+
+impl<N: Clone + Eq + Hash, C: Copy + Ord> Seen<N, C> {
+    fn new() -> Self {
+        Seen {
+            index: HashMap::new(),
+            nodes: Vec::new(),
+        }
+    }
+    // Records a route to `node`, returning its index if it is better than
+    // any seen before.
+    fn improve(&mut self, node: N, cost: C, parent: Option<usize>) -> Option<usize> {
+        match self.index.entry(node) {
+            Entry::Occupied(e) => {
+                let i = *e.get();
+                if cost < self.nodes[i].1 {
+                    self.nodes[i].1 = cost;
+                    self.nodes[i].2 = parent;
+                    Some(i)
+                } else {
+                    None
+                }
+            },
+            Entry::Vacant(e) => {
+                let i = self.nodes.len();
+                self.nodes.push((e.key().clone(), cost, parent));
+                e.insert(i);
+                Some(i)
+            },
+        }
+    }
+    fn path(&self, mut i: usize) -> Path<N, C> {
+        let cost = self.nodes[i].1;
+        let mut nodes = vec![self.nodes[i].0.clone()];
+        while let Some(parent) = self.nodes[i].2 {
+            nodes.push(self.nodes[parent].0.clone());
+            i = parent;
+        }
+        nodes.reverse();
+        Path { cost, nodes }
+    }
+    fn costs(self) -> HashMap<N, C> {
+        self.nodes.into_iter().map(|(node, cost, _)| (node, cost)).collect()
+    }
+}
+
+// Breadth-first search where every edge costs one step.
+pub fn bfs<N, I>(
+    starts: impl IntoIterator<Item=N>,
+    mut successors: impl FnMut(&N) -> I,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Option<Path<N, usize>>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item=N>,
+{
+    let mut seen = Seen::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if let Some(i) = seen.improve(start, 0, None) {
+            queue.push_back(i);
+        }
+    }
+    while let Some(i) = queue.pop_front() {
+        let (node, dist, _) = &seen.nodes[i];
+        if is_goal(node) {
+            return Some(seen.path(i));
+        }
+        let (node, dist) = (node.clone(), *dist);
+        for next in successors(&node) {
+            if !seen.index.contains_key(&next) {
+                let j = seen.improve(next, dist + 1, Some(i)).unwrap();
+                queue.push_back(j);
+            }
+        }
+    }
+    None
+}
+
+// The number of steps to every node reachable from any of the starts.
+pub fn distances<N, I>(
+    starts: impl IntoIterator<Item=N>,
+    mut successors: impl FnMut(&N) -> I,
+) -> HashMap<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item=N>,
+{
+    let mut dists = HashMap::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if let Entry::Vacant(e) = dists.entry(start.clone()) {
+            e.insert(0);
+            queue.push_back(start);
+        }
+    }
+    while let Some(node) = queue.pop_front() {
+        let dist = dists[&node];
+        for next in successors(&node) {
+            if let Entry::Vacant(e) = dists.entry(next.clone()) {
+                e.insert(dist + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    dists
+}
+
+// A* search: `heuristic` must never overestimate the remaining cost to a
+// goal, or the path found may not be the cheapest. It needn't be consistent:
+// a node reached more cheaply after it was expanded is expanded again.
+pub fn astar<N, C, I>(
+    starts: impl IntoIterator<Item=N>,
+    mut successors: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Option<Path<N, C>>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output=C>,
+    I: IntoIterator<Item=(N, C)>,
+{
+    let mut seen = Seen::new();
+    let mut queue = BinaryHeap::new();
+    for start in starts {
+        let estimate = heuristic(&start);
+        if let Some(i) = seen.improve(start, C::default(), None) {
+            queue.push(Reverse((estimate, i)));
+        }
+    }
+    let mut done = vec![false; seen.nodes.len()];
+    while let Some(Reverse((_, i))) = queue.pop() {
+        // A node may be queued again each time a cheaper route to it is
+        // found; only the cheapest of those queued entries counts.
+        if done[i] {
+            continue;
+        }
+        done[i] = true;
+
+        let (node, cost, _) = &seen.nodes[i];
+        if is_goal(node) {
+            return Some(seen.path(i));
+        }
+        let (node, cost) = (node.clone(), *cost);
+        for (next, step) in successors(&node) {
+            let estimate = heuristic(&next);
+            if let Some(j) = seen.improve(next, cost + step, Some(i)) {
+                if j == done.len() {
+                    done.push(false);
+                }
+                done[j] = false;
+                queue.push(Reverse((cost + step + estimate, j)));
+            }
+        }
+    }
+    None
+}
+
+// Dijkstra's algorithm, ie. A* without a heuristic.
+pub fn dijkstra<N, C, I>(
+    starts: impl IntoIterator<Item=N>,
+    successors: impl FnMut(&N) -> I,
+    is_goal: impl FnMut(&N) -> bool,
+) -> Option<Path<N, C>>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output=C>,
+    I: IntoIterator<Item=(N, C)>,
+{
+    astar(starts, successors, |_| C::default(), is_goal)
+}
+
+// The cost of the cheapest route to every node reachable from any of the starts.
+pub fn costs<N, C, I>(
+    starts: impl IntoIterator<Item=N>,
+    mut successors: impl FnMut(&N) -> I,
+) -> HashMap<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output=C>,
+    I: IntoIterator<Item=(N, C)>,
+{
+    let mut seen = Seen::new();
+    let mut queue = BinaryHeap::new();
+    for start in starts {
+        if let Some(i) = seen.improve(start, C::default(), None) {
+            queue.push(Reverse((C::default(), i)));
+        }
+    }
+    while let Some(Reverse((cost, i))) = queue.pop() {
+        if cost > seen.nodes[i].1 {
+            continue;
+        }
+        let node = seen.nodes[i].0.clone();
+        for (next, step) in successors(&node) {
+            if let Some(j) = seen.improve(next, cost + step, Some(i)) {
+                queue.push(Reverse((cost + step, j)));
+            }
+        }
+    }
+    seen.costs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{self, Grid, Point};
+
+    const MAZE: &str = "\
+#######
+#S..#.#
+#.#.#.#
+#.#...#
+#...#G#
+#######";
+
+    fn maze() -> Grid<char> {
+        Grid::parse(MAZE, |_, c| Ok::<_, ()>(c)).unwrap()
+    }
+
+    fn open_neighbours(maze: &Grid<char>, pos: Point) -> Vec<Point> {
+        maze.neighbours(pos)
+            .filter(|&(_, _, &c)| c != '#')
+            .map(|(_, new_pos, _)| new_pos)
+            .collect()
+    }
+
+    #[test]
+    fn bfs_finds_shortest_path() {
+        let maze = maze();
+        let start = maze.find(|&c| c == 'S').unwrap();
+        let goal = maze.find(|&c| c == 'G').unwrap();
+        let path = bfs(vec![start], |&pos| open_neighbours(&maze, pos), |&pos| pos == goal).unwrap();
+        assert_eq!(path.cost, 7);
+        assert_eq!(path.nodes.len(), 8);
+        assert_eq!(*path.start(), start);
+        assert_eq!(*path.goal(), goal);
+        for w in path.nodes.windows(2) {
+            assert_eq!(grid::manhattan(w[0], w[1]), 1);
+            assert_ne!(maze.get(w[1]), Some(&'#'));
+        }
+    }
+
+    #[test]
+    fn distances_from_several_starts() {
+        let dists = distances(vec![0i64, 10], |&n| vec![n - 1, n + 1].into_iter().filter(|n| (0..=10).contains(n)));
+        assert_eq!(dists.len(), 11);
+        assert_eq!(dists[&0], 0);
+        assert_eq!(dists[&3], 3);
+        assert_eq!(dists[&5], 5);
+        assert_eq!(dists[&8], 2);
+    }
+
+    // a -> c directly costs 10, but going via b costs 3.
+    fn weighted(node: &char) -> Vec<(char, u32)> {
+        match node {
+            'a' => vec![('c', 10), ('b', 1)],
+            'b' => vec![('c', 2)],
+            'c' => vec![('d', 1)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn dijkstra_avoids_expensive_direct_edge() {
+        let path = dijkstra(vec!['a'], weighted, |&n| n == 'd').unwrap();
+        assert_eq!(path.cost, 4);
+        assert_eq!(path.nodes, vec!['a', 'b', 'c', 'd']);
+
+        let all = costs(vec!['a'], weighted);
+        assert_eq!(all[&'c'], 3);
+        assert_eq!(all.len(), 4);
+    }
+
+    #[test]
+    fn astar_with_manhattan_heuristic() {
+        let maze = maze();
+        let start = maze.find(|&c| c == 'S').unwrap();
+        let goal = maze.find(|&c| c == 'G').unwrap();
+        let path = astar(
+            vec![start],
+            |&pos| open_neighbours(&maze, pos).into_iter().map(|p| (p, 1)),
+            |&pos| grid::manhattan(pos, goal),
+            |&pos| pos == goal,
+        ).unwrap();
+        assert_eq!(path.cost, 7);
+        assert_eq!(*path.goal(), goal);
+    }
+
+    #[test]
+    fn astar_with_inconsistent_heuristic() {
+        // The heuristic never overestimates, but it makes `b` look worse
+        // than it is, so `c` is expanded before the cheaper route to it
+        // through `b` is found.
+        let successors = |n: &char| match n {
+            'a' => vec![('b', 1), ('c', 3)],
+            'b' => vec![('c', 1)],
+            'c' => vec![('d', 10)],
+            _ => vec![],
+        };
+        let heuristic = |n: &char| match n {
+            'b' => 11,
+            _ => 0,
+        };
+        let path = astar(vec!['a'], successors, heuristic, |&n| n == 'd').unwrap();
+        assert_eq!(path.cost, 12);
+        assert_eq!(path.nodes, vec!['a', 'b', 'c', 'd']);
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        assert_eq!(bfs(vec![0], |&n: &i32| if n < 5 { vec![n + 1] } else { vec![] }, |&n| n == 9), None);
+        assert_eq!(dijkstra(vec!['a'], weighted, |&n| n == 'z'), None);
+        assert_eq!(astar(vec!['a'], weighted, |_| 0, |&n| n == 'z'), None);
+    }
+}
@@ -0,0 +1,113 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::bitmap::Bitmap;
+use crate::pnm::{self, Rgb};
+
+pub const BLACK: u8 = 0;
+pub const WHITE: u8 = 1;
+pub const TRANSPARENT: u8 = 2;
+
+// An image in the Space Image Format: a stack of layers of digits, the
+// first layer being in front.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub layers: Vec<Vec<u8>>,
+}
+
+impl Image {
+    pub fn decode(input: &str, width: usize, height: usize) -> Result<Self, String> {
+        let digits: Vec<u8> = input.trim().chars()
+            .map(|c| c.to_digit(10).map(|d| d as u8).ok_or_else(|| format!("Invalid digit: {:?}", c)))
+            .collect::<Result<_, _>>()?;
+        let size = width*height;
+        if size == 0 || !digits.len().is_multiple_of(size) {
+            return Err(format!("{} digits is not a whole number of {}x{} layers", digits.len(), width, height));
+        }
+        Ok(Image {
+            width,
+            height,
+            layers: digits.chunks(size).map(|layer| layer.to_vec()).collect(),
+        })
+    }
+    // A single layer image.
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), width*height);
+        Image {
+            width,
+            height,
+            layers: vec![pixels],
+        }
+    }
+    pub fn encode(&self) -> String {
+        self.layers.iter()
+            .flatten()
+            .map(|&d| char::from(b'0' + d))
+            .collect()
+    }
+    // How many times each digit appears in each layer.
+    pub fn digit_counts(&self) -> Vec<[usize; 10]> {
+        self.layers.iter().map(|layer| {
+            let mut counts = [0; 10];
+            for &d in layer {
+                counts[d as usize] += 1;
+            }
+            counts
+        }).collect()
+    }
+    // Flattens the layers: each pixel takes the colour of the front-most
+    // layer which is not transparent there.
+    pub fn composite(&self) -> Image {
+        let mut res = vec![TRANSPARENT; self.width*self.height];
+        for layer in &self.layers {
+            for (pixel, &d) in res.iter_mut().zip(layer) {
+                if *pixel == TRANSPARENT {
+                    *pixel = d;
+                }
+            }
+        }
+        Image::from_pixels(self.width, self.height, res)
+    }
+    fn front(&self) -> &[u8] {
+        self.layers.first().map(Vec::as_slice).unwrap_or(&[])
+    }
+    // The white pixels of the composited image.
+    pub fn to_bitmap(&self) -> Bitmap {
+        let composite = self.composite();
+        let mut bitmap = Bitmap::new(self.width, self.height);
+        for (i, &d) in composite.front().iter().enumerate() {
+            bitmap.set(i % self.width, i / self.width, d == WHITE);
+        }
+        bitmap
+    }
+    pub fn to_pbm(&self) -> String {
+        let composite = self.composite();
+        let pixels = composite.front();
+        pnm::pbm(self.width, self.height, |x, y| pixels[y*self.width + x] == BLACK)
+    }
+    // Transparent pixels are shown in grey.
+    pub fn to_ppm(&self) -> String {
+        let composite = self.composite();
+        let pixels = composite.front();
+        pnm::ppm(self.width, self.height, |x, y| -> Rgb {
+            match pixels[y*self.width + x] {
+                BLACK => [0, 0, 0],
+                WHITE => [255, 255, 255],
+                _ => [128, 128, 128],
+            }
+        })
+    }
+    // Saves the composited image as PBM or PPM, depending on the extension.
+    pub fn export(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let data = match path.extension().and_then(|e| e.to_str()) {
+            Some("pbm") => self.to_pbm(),
+            Some("ppm") => self.to_ppm(),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "Expected a .pbm or .ppm file")),
+        };
+        fs::write(path, data)
+    }
+}
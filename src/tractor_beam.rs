@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use crate::intcode::{Io, Program, Value};
+
+// How many diagonals out from the origin to search for a first point of the
+// beam, which guides the search along each row until one has been traced.
+const MAX_SEED_DISTANCE: i64 = 500;
+// Give up looking for a ship position beyond this many rows.
+const MAX_ROWS: i64 = 100_000;
+
+#[derive(Debug)]
+struct IoState {
+    pos: (i64, i64),
+    xy: bool,
+    result: bool,
+}
+
+impl IoState {
+    fn new(pos: (i64, i64)) -> Self {
+        IoState {
+            pos,
+            xy: false,
+            result: false,
+        }
+    }
+}
+
+impl Io for IoState {
+    fn input(&mut self) -> Option<Value> {
+        self.xy = !self.xy;
+        Some(if self.xy {
+            self.pos.0
+        } else {
+            self.pos.1
+        })
+    }
+    fn output(&mut self, value: Value) {
+        self.result = value == 1;
+    }
+}
+
+// A model of the tractor beam built up lazily from drone probes.
+//
+// The beam is a cone from the emitter at the origin, so both of its edges
+// move right (or stay put) from one row to the next. Rows are traced in
+// order, starting each edge search from where the edge was on the previous
+// row, so each row typically costs only a couple of probes.
+#[derive(Debug)]
+pub struct Beam {
+    memory: Vec<Value>,
+    probes: HashMap<(i64, i64), bool>,
+    // Inclusive (left, right) extents of each traced row, or `None` if the
+    // beam misses that row entirely.
+    rows: Vec<Option<(i64, i64)>>,
+    // Some point of the beam below the origin, once it has been looked for.
+    seed: Option<Option<(i64, i64)>>,
+    runs: usize,
+}
+
+impl Beam {
+    pub fn new(memory: Vec<Value>) -> Self {
+        Beam {
+            memory,
+            probes: HashMap::new(),
+            rows: Vec::new(),
+            seed: None,
+            runs: 0,
+        }
+    }
+    // Number of times the drone program has been run so far.
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+    pub fn probe(&mut self, x: i64, y: i64) -> bool {
+        if x < 0 || y < 0 {
+            return false;
+        }
+        if let Some(&result) = self.probes.get(&(x, y)) {
+            return result;
+        }
+        let mut program = Program::new(self.memory.clone(), IoState::new((x, y)));
+        program.run();
+        self.runs += 1;
+        self.probes.insert((x, y), program.io.result);
+        program.io.result
+    }
+    pub fn contains(&mut self, x: i64, y: i64) -> bool {
+        match self.rows.get(y as usize) {
+            Some(&Some((left, right))) if y >= 0 => left <= x && x <= right,
+            Some(&None) if y >= 0 => false,
+            _ => self.probe(x, y),
+        }
+    }
+    pub fn row(&mut self, y: i64) -> Option<(i64, i64)> {
+        if y < 0 {
+            return None;
+        }
+        while self.rows.len() <= y as usize {
+            let extents = self.trace_row(self.rows.len() as i64);
+            self.rows.push(extents);
+        }
+        self.rows[y as usize]
+    }
+    // Searches the diagonals `x + y = d` outwards from the origin, so the beam
+    // is found however steep it is.
+    fn seed(&mut self) -> Option<(i64, i64)> {
+        if let Some(seed) = self.seed {
+            return seed;
+        }
+        let seed = (1..=MAX_SEED_DISTANCE)
+            .flat_map(|d| (0..d).map(move |x| (x, d - x)))
+            .find(|&(x, y)| self.probe(x, y));
+        self.seed = Some(seed);
+        seed
+    }
+    fn trace_row(&mut self, y: i64) -> Option<(i64, i64)> {
+        let prev = self.rows.iter()
+            .enumerate()
+            .rev()
+            .filter(|&(prev_y, _)| prev_y > 0)
+            .find_map(|(prev_y, &extents)| extents.map(|e| (prev_y as i64, e)));
+
+        let (mut left, limit) = match prev {
+            Some((prev_y, (prev_left, prev_right))) => {
+                (prev_left, ((prev_right + 1)*y + prev_y - 1)/prev_y + 1)
+            },
+            // Until a row has been traced, the beam's position on this row
+            // is estimated from the seed. The beam is a cone, so the point
+            // on the line through the seed lies within it.
+            None => match self.seed() {
+                Some((seed_x, seed_y)) => (0, ((seed_x + 1)*y + seed_y - 1)/seed_y + 1),
+                None => return None,
+            },
+        };
+        while !self.probe(left, y) {
+            left += 1;
+            if left > limit {
+                return None;
+            }
+        }
+
+        let mut right = match prev {
+            Some((_, (_, prev_right))) => prev_right.max(left),
+            None => left,
+        };
+        while right > left && !self.probe(right, y) {
+            right -= 1;
+        }
+        while self.probe(right + 1, y) {
+            right += 1;
+        }
+        Some((left, right))
+    }
+    // Finds the position closest to the emitter at which a `width` x `height`
+    // ship fits entirely within the beam, returning its top-left corner.
+    pub fn fit(&mut self, width: i64, height: i64) -> Option<(i64, i64)> {
+        for bottom in (height - 1)..MAX_ROWS {
+            let top = bottom - height + 1;
+            let (left, bottom_right) = match self.row(bottom) {
+                Some(extents) => extents,
+                None => continue,
+            };
+            if bottom_right < left + width - 1 {
+                continue;
+            }
+            if let Some((top_left, top_right)) = self.row(top) {
+                if top_left <= left && top_right >= left + width - 1 {
+                    return Some((left, top));
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,556 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use crate::grid::{self, Direction, Grid, Point};
+use crate::search;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Cell {
+    Empty,
+    Wall,
+    Key(usize),
+    Door(usize),
+}
+
+// A set of keys, which may be numbered beyond 64.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct KeySet {
+    // Never has trailing zero words, so equal sets compare equal.
+    words: Vec<u64>,
+}
+
+impl KeySet {
+    pub fn new() -> Self {
+        KeySet::default()
+    }
+    pub fn contains(&self, key: usize) -> bool {
+        self.words.get(key / 64).is_some_and(|&w| w & (1 << (key % 64)) != 0)
+    }
+    pub fn insert(&mut self, key: usize) {
+        if self.words.len() <= key / 64 {
+            self.words.resize(key / 64 + 1, 0);
+        }
+        self.words[key / 64] |= 1 << (key % 64);
+    }
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+    pub fn iter(&self) -> impl Iterator<Item=usize> + '_ {
+        (0..self.words.len()*64).filter(move |&key| self.contains(key))
+    }
+}
+
+// A parsed vault map. Entrances are stored separately and left as empty cells.
+// Keys are any lowercase letters, and doors the matching uppercase letters, so
+// alphabets other than the Latin one can be used for more than 26 keys.
+#[derive(Debug, Clone)]
+pub struct Vault {
+    pub map: Grid<Cell>,
+    pub entrances: Vec<Point>,
+    // The name of each key, by number.
+    pub key_names: Vec<char>,
+    // Every key which appears in the vault, as opposed to only on a door.
+    pub all_keys: KeySet,
+}
+
+impl Vault {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut entrances = Vec::new();
+        let mut key_names = Vec::new();
+        let mut key_ids = HashMap::new();
+        let mut all_keys = KeySet::new();
+        let mut key_id = |name: char| *key_ids.entry(name).or_insert_with(|| {
+            key_names.push(name);
+            key_names.len() - 1
+        });
+        let map = Grid::parse(input, |pos, c| {
+            if c == '@' {
+                entrances.push(pos);
+            }
+            Ok(match c {
+                '.' | '@' => Cell::Empty,
+                // Spaces pad out short lines.
+                '#' | ' ' => Cell::Wall,
+                c if c.is_lowercase() => {
+                    let key = key_id(c);
+                    all_keys.insert(key);
+                    Cell::Key(key)
+                },
+                c if c.is_uppercase() => {
+                    let mut lower = c.to_lowercase();
+                    match (lower.next(), lower.next()) {
+                        (Some(name), None) => Cell::Door(key_id(name)),
+                        _ => return Err(format!("Door has no single key: {}", c)),
+                    }
+                },
+                _ => return Err(format!("Unexpected character: {:?}", c)),
+            })
+        })?;
+        if entrances.is_empty() {
+            return Err("Vault has no entrance".into());
+        }
+        Ok(Vault { map, entrances, key_names, all_keys })
+    }
+    pub fn key_name(&self, key: usize) -> char {
+        self.key_names[key]
+    }
+    pub fn door_name(&self, key: usize) -> char {
+        self.key_names[key].to_uppercase().next().unwrap()
+    }
+    // Anything off the edge of the map counts as a wall.
+    pub fn cell(&self, pos: Point) -> Cell {
+        self.map.get(pos).copied().unwrap_or(Cell::Wall)
+    }
+    // Walls off the single entrance and its neighbours, leaving four
+    // entrances on the diagonals. Fails unless there's exactly one entrance
+    // with open cells on every diagonal.
+    pub fn split_entrance(&mut self) -> Result<(), String> {
+        if self.entrances.len() != 1 {
+            return Err(format!("Can only split a single entrance, not {}", self.entrances.len()));
+        }
+        let pos = self.entrances[0];
+        let entrances = vec![
+            (pos.0-1, pos.1-1),
+            (pos.0-1, pos.1+1),
+            (pos.0+1, pos.1-1),
+            (pos.0+1, pos.1+1),
+        ];
+        if entrances.iter().any(|&new_pos| self.cell(new_pos) == Cell::Wall) {
+            return Err(format!("No room to split the entrance at {:?}", pos));
+        }
+        self.map.set(pos, Cell::Wall);
+        for &dir in &Direction::ALL {
+            self.map.set(dir.apply(pos), Cell::Wall);
+        }
+        self.entrances = entrances;
+        Ok(())
+    }
+    fn open_neighbours(&self, pos: Point) -> impl Iterator<Item=Point> + '_ {
+        grid::neighbours(pos)
+            .map(|(_, new_pos)| new_pos)
+            .filter(move |&new_pos| self.cell(new_pos) != Cell::Wall)
+    }
+    // The vault with each robot's route drawn over it, using the robot's
+    // number (from 1) for every cell it passes through.
+    pub fn render_route(&self, solution: &Solution) -> String {
+        let mut overlay = HashMap::new();
+        for (robot, route) in solution.routes(self.entrances.len()).iter().enumerate() {
+            let mark = std::char::from_digit((robot as u32 + 1) % 10, 10).unwrap();
+            for &pos in route {
+                overlay.insert(pos, mark);
+            }
+        }
+        let mut res = String::new();
+        for y in 0..self.map.height as i64 {
+            for x in 0..self.map.width as i64 {
+                let pos = (x, y);
+                res.push(match self.cell(pos) {
+                    _ if self.entrances.contains(&pos) => '@',
+                    Cell::Key(k) => self.key_name(k),
+                    Cell::Door(k) => self.door_name(k),
+                    Cell::Wall => '#',
+                    Cell::Empty => overlay.get(&pos).copied().unwrap_or('.'),
+                });
+            }
+            res.push('\n');
+        }
+        res
+    }
+}
+
+// One robot walking from where it last stopped to the next key it collects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leg {
+    pub robot: usize,
+    pub key: char,
+    pub steps: usize,
+    // Every cell visited, from where the robot started to the key.
+    pub route: Vec<Point>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub steps: usize,
+    pub legs: Vec<Leg>,
+}
+
+impl Solution {
+    // The keys in the order they were collected.
+    pub fn key_order(&self) -> String {
+        self.legs.iter().map(|leg| leg.key).collect()
+    }
+    // The full route taken by each robot.
+    pub fn routes(&self, robots: usize) -> Vec<Vec<Point>> {
+        let mut routes = vec![Vec::new(); robots];
+        for leg in &self.legs {
+            let route = &mut routes[leg.robot];
+            let skip = if route.is_empty() { 0 } else { 1 };
+            route.extend(leg.route.iter().skip(skip));
+        }
+        routes
+    }
+}
+
+struct Edge {
+    node_id: usize,
+    dist: usize,
+}
+
+struct Node {
+    pos: Point,
+    cell: Cell,
+    edges: Vec<Edge>,
+}
+
+// The vault reduced to its entrances, keys and doors, joined by the shortest
+// walks between them which don't pass any other key or door.
+struct Graph<'a> {
+    vault: &'a Vault,
+    nodes: Vec<Node>,
+    node_map: HashMap<Point, usize>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(vault: &'a Vault) -> Self {
+        let mut graph = Graph {
+            vault,
+            nodes: Vec::new(),
+            node_map: HashMap::new(),
+        };
+        for &pos in &vault.entrances {
+            graph.add_node(pos);
+        }
+        graph
+    }
+    fn is_stop(&self, pos: Point, from: Point) -> bool {
+        pos != from && matches!(self.vault.cell(pos), Cell::Door(_) | Cell::Key(_))
+    }
+    fn successors(&self, pos: Point, from: Point) -> Vec<Point> {
+        if self.is_stop(pos, from) {
+            return Vec::new();
+        }
+        self.vault.open_neighbours(pos).collect()
+    }
+    fn add_node(&mut self, node_pos: Point) -> usize {
+        if let Some(&node_id) = self.node_map.get(&node_pos) {
+            return node_id;
+        }
+
+        let cell = self.vault.cell(node_pos);
+        let node_id = self.nodes.len();
+        self.nodes.push(Node {
+            pos: node_pos,
+            cell,
+            edges: Vec::new(),
+        });
+        self.node_map.insert(node_pos, node_id);
+
+        // Walk outwards until reaching another key or door, which become
+        // the ends of this node's edges.
+        let dists = search::distances(vec![node_pos], |&pos| self.successors(pos, node_pos));
+
+        for (pos, dist) in dists {
+            if self.is_stop(pos, node_pos) {
+                let other_node_id = self.add_node(pos);
+                self.nodes[node_id].edges.push(Edge {
+                    node_id: other_node_id,
+                    dist,
+                });
+            }
+        }
+        node_id
+    }
+    // The cells walked along the edge between two nodes.
+    fn route(&self, from: usize, to: usize) -> Vec<Point> {
+        let (from, to) = (self.nodes[from].pos, self.nodes[to].pos);
+        search::bfs(vec![from], |&pos| self.successors(pos, from), |&pos| pos == to)
+            .expect("Edge has no route")
+            .nodes
+    }
+    // The node holding each key, if it can be reached at all.
+    fn key_nodes(&self) -> Vec<Option<usize>> {
+        let mut res = vec![None; self.vault.key_names.len()];
+        for (node_id, node) in self.nodes.iter().enumerate() {
+            if let Cell::Key(k) = node.cell {
+                res[k] = Some(node_id);
+            }
+        }
+        res
+    }
+    // The distance from every node to every key, ignoring doors. This never
+    // overestimates how far a robot has to walk to fetch a key.
+    fn key_distances(&self, key_nodes: &[Option<usize>]) -> Vec<Vec<Option<usize>>> {
+        (0..self.nodes.len()).map(|from| {
+            let costs = search::costs(vec![from], |&node_id| {
+                self.nodes[node_id].edges.iter()
+                    .map(|edge| (edge.node_id, edge.dist))
+                    .collect::<Vec<_>>()
+            });
+            key_nodes.iter()
+                .map(|key_node| key_node.and_then(|node_id| costs.get(&node_id).copied()))
+                .collect()
+        }).collect()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct State {
+    // The node each robot is at.
+    robots: Vec<usize>,
+    keys: KeySet,
+}
+
+// Collects every key using one robot per entrance, searching over the graph of
+// keys and doors. The search is guided by the distance to the furthest key
+// still to be collected, which lets it skip most hopeless orders.
+pub fn solve(vault: &Vault) -> Option<Solution> {
+    let graph = Graph::new(vault);
+    let robots = vault.entrances.len();
+    let key_nodes = graph.key_nodes();
+    if vault.all_keys.iter().any(|k| key_nodes[k].is_none()) {
+        return None;
+    }
+    let key_dists = graph.key_distances(&key_nodes);
+
+    let heuristic = |state: &State| -> usize {
+        vault.all_keys.iter()
+            .filter(|&k| !state.keys.contains(k))
+            .map(|k| state.robots.iter().filter_map(|&node_id| key_dists[node_id][k]).min().unwrap_or(0))
+            .max()
+            .unwrap_or(0)
+    };
+
+    let path = search::astar(
+        vec![State {
+            robots: vault.entrances.iter().map(|pos| graph.node_map[pos]).collect(),
+            keys: KeySet::new(),
+        }],
+        |state| {
+            let mut res = Vec::new();
+            for robot in 0..robots {
+                let node = &graph.nodes[state.robots[robot]];
+                for edge in node.edges.iter() {
+                    let mut keys = state.keys.clone();
+                    match graph.nodes[edge.node_id].cell {
+                        Cell::Key(k) => keys.insert(k),
+                        Cell::Door(k) if !keys.contains(k) => continue,
+                        _ => {},
+                    }
+                    let mut new_robots = state.robots.clone();
+                    new_robots[robot] = edge.node_id;
+                    res.push((State { robots: new_robots, keys }, edge.dist));
+                }
+            }
+            res
+        },
+        heuristic,
+        |state| state.keys == vault.all_keys,
+    )?;
+
+    // Each step of the path moves one robot along one edge. A robot's leg
+    // continues through doors until it picks up a key.
+    let mut legs = Vec::new();
+    let mut pending: Vec<Vec<Point>> = vec![Vec::new(); robots];
+    for w in path.nodes.windows(2) {
+        let (old, new) = (&w[0], &w[1]);
+        let robot = (0..robots).find(|&r| old.robots[r] != new.robots[r]).unwrap();
+        let route = graph.route(old.robots[robot], new.robots[robot]);
+        let leg = &mut pending[robot];
+        let skip = if leg.is_empty() { 0 } else { 1 };
+        leg.extend(route.into_iter().skip(skip));
+        if let Some(key) = new.keys.iter().find(|&k| !old.keys.contains(k)) {
+            let route = std::mem::take(leg);
+            legs.push(Leg {
+                robot,
+                key: vault.key_name(key),
+                steps: route.len() - 1,
+                route,
+            });
+        }
+    }
+    Some(Solution { steps: path.cost, legs })
+}
+
+// Problems found by `Vault::validate`, and which keys depend on which.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    // Doors whose key appears nowhere in the vault.
+    pub doors_without_keys: Vec<char>,
+    // Keys which can't be reached from any entrance even with every door open.
+    pub unreachable_keys: Vec<char>,
+    // Keys which can only be reached through one of their own doors.
+    pub keys_behind_own_doors: Vec<char>,
+    // For each key, the other keys needed to open every route to it, without
+    // any which are implied by another dependency.
+    pub dependencies: BTreeMap<char, Vec<char>>,
+    // Keys which end up depending on themselves through other keys.
+    pub cycle: Option<Vec<char>>,
+}
+
+impl Report {
+    pub fn is_solvable(&self) -> bool {
+        let missing = self.dependencies.values()
+            .flatten()
+            .any(|key| self.doors_without_keys.contains(key));
+        !missing
+            && self.unreachable_keys.is_empty()
+            && self.keys_behind_own_doors.is_empty()
+            && self.cycle.is_none()
+    }
+    // The dependency graph, with an edge from each key to the keys it needs.
+    // Keys which are missing are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut s = String::from("digraph keys {\n");
+        for (key, deps) in &self.dependencies {
+            s += &format!("    \"{}\";\n", key);
+            for dep in deps {
+                s += &format!("    \"{}\" -> \"{}\";\n", key, dep);
+            }
+        }
+        for key in &self.doors_without_keys {
+            s += &format!("    \"{}\" [style=dashed];\n", key);
+        }
+        s += "}\n";
+        s
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn list(keys: &[char]) -> String {
+            keys.iter().collect()
+        }
+
+        if !self.doors_without_keys.is_empty() {
+            let doors: String = self.doors_without_keys.iter().flat_map(|c| c.to_uppercase()).collect();
+            writeln!(f, "Doors without keys: {}", doors)?;
+        }
+        if !self.unreachable_keys.is_empty() {
+            writeln!(f, "Unreachable keys: {}", list(&self.unreachable_keys))?;
+        }
+        if !self.keys_behind_own_doors.is_empty() {
+            writeln!(f, "Keys behind their own doors: {}", list(&self.keys_behind_own_doors))?;
+        }
+        if let Some(cycle) = &self.cycle {
+            writeln!(f, "Keys depending on each other: {}", list(cycle))?;
+        }
+        for (key, deps) in &self.dependencies {
+            if !deps.is_empty() {
+                writeln!(f, "{} needs {}", key, list(deps))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Vault {
+    // Every open cell reachable from an entrance without passing the doors
+    // of `closed`.
+    fn reachable(&self, closed: Option<usize>) -> HashMap<Point, usize> {
+        search::distances(self.entrances.clone(), |&pos| {
+            self.open_neighbours(pos)
+                .filter(|&new_pos| !matches!((self.cell(new_pos), closed), (Cell::Door(k), Some(c)) if k == c))
+                .collect::<Vec<_>>()
+        })
+    }
+    // Checks the vault can be solved at all, which is much quicker than
+    // finding out from a search which never reaches every key.
+    pub fn validate(&self) -> Report {
+        let mut report = Report::default();
+        let mut key_pos = HashMap::new();
+        let mut doors = BTreeSet::new();
+        for (pos, &cell) in self.map.iter() {
+            match cell {
+                Cell::Key(k) => { key_pos.insert(k, pos); },
+                Cell::Door(k) => { doors.insert(k); },
+                _ => {},
+            }
+        }
+        report.doors_without_keys = doors.iter()
+            .filter(|&&k| !self.all_keys.contains(k))
+            .map(|&k| self.key_name(k))
+            .collect();
+
+        let open = self.reachable(None);
+        let mut keys: Vec<usize> = self.all_keys.iter().collect();
+        keys.sort_by_key(|&k| self.key_name(k));
+        for &k in &keys {
+            if !open.contains_key(&key_pos[&k]) {
+                report.unreachable_keys.push(self.key_name(k));
+            }
+        }
+
+        // A key needs another key if closing that key's doors cuts it off.
+        let mut needs: BTreeMap<usize, BTreeSet<usize>> = keys.iter().map(|&k| (k, BTreeSet::new())).collect();
+        for &door in &doors {
+            let reachable = self.reachable(Some(door));
+            for &k in &keys {
+                let pos = key_pos[&k];
+                if open.contains_key(&pos) && !reachable.contains_key(&pos) {
+                    if k == door {
+                        report.keys_behind_own_doors.push(self.key_name(k));
+                    } else {
+                        needs.get_mut(&k).unwrap().insert(door);
+                    }
+                }
+            }
+        }
+
+        report.cycle = find_cycle(&needs).map(|cycle| cycle.into_iter().map(|k| self.key_name(k)).collect());
+
+        // Leave out dependencies which follow from another one.
+        for (&k, deps) in &needs {
+            let mut direct: Vec<char> = deps.iter()
+                .filter(|&&d| !deps.iter().any(|&e| e != d && transitive(&needs, e).contains(&d)))
+                .map(|&d| self.key_name(d))
+                .collect();
+            direct.sort();
+            report.dependencies.insert(self.key_name(k), direct);
+        }
+        report
+    }
+}
+
+// Every key `key` depends on, directly or not.
+fn transitive(needs: &BTreeMap<usize, BTreeSet<usize>>, key: usize) -> BTreeSet<usize> {
+    let mut res = BTreeSet::new();
+    let mut stack = vec![key];
+    while let Some(k) = stack.pop() {
+        for &d in needs.get(&k).into_iter().flatten() {
+            if res.insert(d) {
+                stack.push(d);
+            }
+        }
+    }
+    res
+}
+
+fn find_cycle(needs: &BTreeMap<usize, BTreeSet<usize>>) -> Option<Vec<usize>> {
+    fn visit(
+        needs: &BTreeMap<usize, BTreeSet<usize>>,
+        key: usize,
+        path: &mut Vec<usize>,
+        done: &mut BTreeSet<usize>,
+    ) -> Option<Vec<usize>> {
+        if let Some(i) = path.iter().position(|&k| k == key) {
+            return Some(path[i..].to_vec());
+        }
+        if !done.insert(key) {
+            return None;
+        }
+        path.push(key);
+        for &d in needs.get(&key).into_iter().flatten() {
+            if let Some(cycle) = visit(needs, d, path, done) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        None
+    }
+
+    let mut done = BTreeSet::new();
+    needs.keys().find_map(|&k| visit(needs, k, &mut Vec::new(), &mut done))
+}